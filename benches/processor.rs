@@ -0,0 +1,26 @@
+use raug_macros::{bench_processor, processor};
+
+#[processor(derive(Default))]
+pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+    x * g
+}
+
+#[processor(derive(Default))]
+pub fn one_pole(#[state] y: &mut f32, #[input] x: &f32, #[input] coeff: &f32) -> f32 {
+    *y += (x - *y) * coeff;
+    *y
+}
+
+// Both inputs are driven by the same signal, so the gain squares it.
+bench_processor!(Gain, baseline = |x: f32| x * x);
+bench_processor!(
+    OnePole,
+    processor = OnePole {
+        coeff: 0.1,
+        ..Default::default()
+    },
+    block_size = 64,
+);
+
+criterion::criterion_group!(benches, bench_gain, bench_one_pole);
+criterion::criterion_main!(benches);
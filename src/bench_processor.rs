@@ -0,0 +1,160 @@
+use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+
+struct BenchProcessor {
+    ty: syn::Path,
+    name: Option<syn::Ident>,
+    processor: Option<syn::Expr>,
+    block_size: Option<syn::Expr>,
+    sample_rate: Option<syn::Expr>,
+    baseline: Option<syn::Expr>,
+    criterion: Option<syn::Path>,
+}
+
+impl syn::parse::Parse for BenchProcessor {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut bench = Self {
+            ty: input.parse()?,
+            name: None,
+            processor: None,
+            block_size: None,
+            sample_rate: None,
+            baseline: None,
+            criterion: None,
+        };
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<syn::Ident>()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "name" {
+                bench.name = Some(input.parse()?);
+            } else if key == "processor" {
+                bench.processor = Some(input.parse()?);
+            } else if key == "block_size" {
+                bench.block_size = Some(input.parse()?);
+            } else if key == "sample_rate" {
+                bench.sample_rate = Some(input.parse()?);
+            } else if key == "baseline" {
+                bench.baseline = Some(input.parse()?);
+            } else if key == "criterion" {
+                bench.criterion = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "Unknown option. Only `name`, `processor`, `block_size`, `sample_rate`, `baseline` and `criterion` are supported.",
+                ));
+            }
+        }
+        Ok(bench)
+    }
+}
+
+pub fn bench_processor(input: TokenStream) -> TokenStream {
+    let bench = syn::parse_macro_input!(input as BenchProcessor);
+
+    let ty = &bench.ty;
+    let Some(type_name) = ty.segments.last().map(|segment| &segment.ident) else {
+        return syn::Error::new_spanned(ty, "Expected a processor type")
+            .to_compile_error()
+            .into();
+    };
+    let name = bench
+        .name
+        .clone()
+        .unwrap_or_else(|| format_ident!("bench_{}", type_name.to_string().to_case(Case::Snake)));
+    let processor = bench.processor.as_ref().map_or_else(
+        || quote! { <#ty as Default>::default() },
+        |expr| quote! { #expr },
+    );
+    let block_size = bench
+        .block_size
+        .as_ref()
+        .map_or_else(|| quote! { 512 }, |expr| quote! { #expr });
+    let sample_rate = bench
+        .sample_rate
+        .as_ref()
+        .map_or_else(|| quote! { 48_000.0 }, |expr| quote! { #expr });
+    let baseline = bench
+        .baseline
+        .as_ref()
+        .map_or_else(|| quote! { |x: f32| x }, |expr| quote! { #expr });
+    let criterion = bench
+        .criterion
+        .as_ref()
+        .map_or_else(|| quote! { criterion }, |path| quote! { #path });
+
+    let doc = format!(
+        "Benchmarks [`{}`] against a scalar baseline, reporting throughput in samples per second.",
+        type_name
+    );
+
+    quote! {
+        #[doc = #doc]
+        pub fn #name(c: &mut #criterion::Criterion) {
+            use raug::processor::Processor;
+            use raug::signal::{type_erased::AnyBuffer, Signal};
+
+            let mut processor = #processor;
+            let block_size: usize = #block_size;
+            let sample_rate: f32 = #sample_rate;
+            let baseline: fn(f32) -> f32 = #baseline;
+
+            // Every `f32` input is driven by the same test signal; other inputs are unconnected.
+            let signal = (0..block_size)
+                .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / sample_rate).sin())
+                .collect::<Vec<_>>();
+            let input_spec = processor.input_spec();
+            let output_spec = processor.output_spec();
+            let inputs = input_spec
+                .iter()
+                .map(|spec| {
+                    (spec.signal_type == f32::signal_type()).then(|| {
+                        let mut buffer = AnyBuffer::zeros::<f32>(block_size);
+                        if let Some(buffer) = buffer.as_mut_slice::<f32>() {
+                            buffer.copy_from_slice(&signal);
+                        }
+                        buffer
+                    })
+                })
+                .collect::<Vec<_>>();
+            let inputs = inputs.iter().map(Option::as_ref).collect::<Vec<_>>();
+
+            processor.allocate(sample_rate, block_size);
+            let mut outputs = processor.create_output_buffers(block_size);
+            let env = raug::processor::io::ProcEnv {
+                sample_rate,
+                block_size,
+                mode: raug::processor::io::ProcessMode::Block,
+            };
+
+            let mut group = c.benchmark_group(stringify!(#type_name));
+            group.throughput(#criterion::Throughput::Elements(block_size as u64));
+            group.bench_function("process", |b| {
+                b.iter(|| {
+                    if let Err(err) = processor.process(
+                        raug::processor::io::ProcessorInputs::new(&input_spec, std::hint::black_box(&inputs), env),
+                        raug::processor::io::ProcessorOutputs::new(&output_spec, &mut outputs, raug::processor::io::ProcessMode::Block),
+                    ) {
+                        panic!("{} failed: {:?}", stringify!(#type_name), err);
+                    }
+                    std::hint::black_box(&mut outputs);
+                })
+            });
+            let mut scalar = vec![0.0f32; block_size];
+            group.bench_function("scalar baseline", |b| {
+                b.iter(|| {
+                    for (out, x) in scalar.iter_mut().zip(std::hint::black_box(&signal)) {
+                        *out = baseline(*x);
+                    }
+                    std::hint::black_box(&mut scalar);
+                })
+            });
+            group.finish();
+        }
+    }
+    .into()
+}
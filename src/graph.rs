@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{punctuated::Punctuated, spanned::Spanned, visit_mut::VisitMut};
+
+use math::Lower;
+
+mod math;
+
+/// An argument of a node, connected either by position or by input name.
+enum NodeArg {
+    Positional(syn::Expr),
+    Named(syn::Ident, syn::Expr),
+}
+
+impl syn::parse::Parse for NodeArg {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Ident) && input.peek2(syn::Token![:]) && !input.peek2(syn::Token![::]) {
+            let name = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            Ok(Self::Named(name, input.parse()?))
+        } else {
+            Ok(Self::Positional(input.parse()?))
+        }
+    }
+}
+
+enum Statement {
+    /// `name = Type(args);`
+    Node {
+        name: syn::Ident,
+        ty: syn::Path,
+        args: Vec<NodeArg>,
+    },
+    /// `name.port <- expr;`
+    Connect {
+        node: syn::Ident,
+        port: syn::Ident,
+        expr: syn::Expr,
+    },
+    /// `dac <- expr, ...;`
+    Dac { outputs: Vec<syn::Expr> },
+}
+
+impl syn::parse::Parse for Statement {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<syn::Ident>()?;
+        let statement = if input.peek(syn::Token![=]) {
+            input.parse::<syn::Token![=]>()?;
+            let ty = input.parse()?;
+            let args = if input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in input);
+                Punctuated::<NodeArg, syn::Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![]
+            };
+            Statement::Node { name, ty, args }
+        } else if input.peek(syn::Token![.]) {
+            input.parse::<syn::Token![.]>()?;
+            let port = input.parse()?;
+            input.parse::<syn::Token![<-]>()?;
+            Statement::Connect {
+                node: name,
+                port,
+                expr: input.parse()?,
+            }
+        } else if input.peek(syn::Token![<-]) {
+            if name != "dac" {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "Only `dac` can be connected to without a port, e.g. `node.port <- ...`",
+                ));
+            }
+            input.parse::<syn::Token![<-]>()?;
+            let outputs = Punctuated::<syn::Expr, syn::Token![,]>::parse_separated_nonempty(input)?;
+            Statement::Dac {
+                outputs: outputs.into_iter().collect(),
+            }
+        } else {
+            return Err(
+                input.error("Expected `name = Type(...)`, `name.port <- ...` or `dac <- ...`")
+            );
+        };
+        if !input.is_empty() {
+            input.parse::<syn::Token![;]>()?;
+        }
+        Ok(statement)
+    }
+}
+
+struct GraphMacro {
+    graph: Option<syn::Expr>,
+    statements: Vec<Statement>,
+}
+
+impl syn::parse::Parse for GraphMacro {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        // An existing graph is given as `graph => ...`.
+        let fork = input.fork();
+        let graph = match fork.parse::<syn::Expr>() {
+            Ok(graph) if fork.peek(syn::Token![=>]) => {
+                input.parse::<syn::Expr>()?;
+                input.parse::<syn::Token![=>]>()?;
+                Some(graph)
+            }
+            _ => None,
+        };
+        let mut statements = vec![];
+        while !input.is_empty() {
+            statements.push(input.parse()?);
+        }
+        Ok(Self { graph, statements })
+    }
+}
+
+/// Rewrites `node.port` into `node.output(index)` for the nodes defined so far.
+struct ResolveOutputs<'a> {
+    nodes: &'a HashMap<syn::Ident, syn::Path>,
+}
+
+impl VisitMut for ResolveOutputs<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if let syn::Expr::Field(field) = expr {
+            if let (syn::Expr::Path(base), syn::Member::Named(port)) = (&*field.base, &field.member)
+            {
+                if let Some(ty) = base.path.get_ident().and_then(|name| self.nodes.get(name)) {
+                    let node = &base.path;
+                    let index = format_ident!("__output_{}", port, span = port.span());
+                    *expr = syn::parse_quote! { #node.output(<#ty>::#index) };
+                    return;
+                }
+            }
+        }
+        syn::visit_mut::visit_expr_mut(self, expr);
+    }
+}
+
+/// Returns the variable holding the node that a graph node's inputs are connected to, which is
+/// only different from the node itself for a `#[subgraph]`.
+fn inputs_node(node: &syn::Ident) -> syn::Ident {
+    format_ident!("__{}_inputs", node)
+}
+
+fn connect(
+    nodes: &HashMap<syn::Ident, syn::Path>,
+    node: &syn::Ident,
+    index: proc_macro2::TokenStream,
+    expr: &syn::Expr,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let value = Lower { nodes }.lower(expr)?;
+    Ok(quote! {
+        if let Some(output) = raug::graph::node::IntoOutputOpt::into_output_opt(#value, __graph) {
+            #node.input(#index).connect(output);
+        }
+    })
+}
+
+pub fn graph(input: TokenStream) -> TokenStream {
+    let GraphMacro { graph, statements } = syn::parse_macro_input!(input as GraphMacro);
+
+    let mut nodes = HashMap::new();
+    let mut body = vec![];
+    let mut errors: Option<syn::Error> = None;
+    let mut push_error = |error: syn::Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+    for statement in statements {
+        match statement {
+            Statement::Node { name, ty, args } => {
+                if name == "dac" {
+                    push_error(syn::Error::new_spanned(
+                        &name,
+                        "`dac` is reserved for the graph's audio outputs",
+                    ));
+                    continue;
+                }
+                if nodes.contains_key(&name) {
+                    push_error(syn::Error::new_spanned(
+                        &name,
+                        format!("Duplicate node `{}`", name),
+                    ));
+                    continue;
+                }
+                let inputs = inputs_node(&name);
+                let mut connections = vec![];
+                let mut named = vec![];
+                let positional = args
+                    .iter()
+                    .take_while(|arg| matches!(arg, NodeArg::Positional(_)))
+                    .count();
+                for (index, arg) in args.iter().enumerate() {
+                    let connection = match arg {
+                        NodeArg::Positional(expr) if index >= positional => {
+                            push_error(syn::Error::new_spanned(
+                                expr,
+                                "Positional arguments must come before named arguments",
+                            ));
+                            continue;
+                        }
+                        NodeArg::Positional(expr) => {
+                            let message = format!(
+                                "`{}` has fewer inputs than positional arguments",
+                                ty.to_token_stream().to_string().replace(' ', ""),
+                            );
+                            connections.push(quote_spanned! {expr.span()=>
+                                const _: () = assert!(#index < <#ty>::__INPUT_COUNT, #message);
+                            });
+                            connect(&nodes, &inputs, quote! { #index }, expr)
+                        }
+                        NodeArg::Named(port, expr) => {
+                            if named.contains(port) {
+                                push_error(syn::Error::new_spanned(
+                                    port,
+                                    format!("Input `{}` is connected more than once", port),
+                                ));
+                            }
+                            named.push(port.clone());
+                            let index = format_ident!("__input_{}", port, span = port.span());
+                            if positional > 0 {
+                                let message = format!(
+                                    "Input `{}` is connected both by position and by name",
+                                    port
+                                );
+                                connections.push(quote_spanned! {port.span()=>
+                                    const _: () = assert!(<#ty>::#index >= #positional, #message);
+                                });
+                            }
+                            connect(&nodes, &inputs, quote! { <#ty>::#index }, expr)
+                        }
+                    };
+                    match connection {
+                        Ok(connection) => connections.push(connection),
+                        Err(err) => push_error(err),
+                    }
+                }
+                body.push(quote! {
+                    let (#inputs, #name) = <#ty as Default>::default().__graph_node(__graph);
+                    #(#connections)*
+                });
+                nodes.insert(name, ty);
+            }
+            Statement::Connect { node, port, expr } => {
+                let Some(ty) = nodes.get(&node) else {
+                    push_error(syn::Error::new_spanned(
+                        &node,
+                        format!("`{}` is not a node in this graph", node),
+                    ));
+                    continue;
+                };
+                let index = format_ident!("__input_{}", port, span = port.span());
+                match connect(&nodes, &inputs_node(&node), quote! { <#ty>::#index }, &expr) {
+                    Ok(connection) => body.push(connection),
+                    Err(err) => push_error(err),
+                }
+            }
+            Statement::Dac { outputs } => {
+                for expr in outputs.iter() {
+                    match connect(&nodes, &format_ident!("__dac"), quote! { 0 }, expr) {
+                        Ok(connection) => body.push(quote! {{
+                            let __dac = __graph.add_audio_output();
+                            #connection
+                        }}),
+                        Err(err) => push_error(err),
+                    }
+                }
+            }
+        }
+    }
+    if let Some(errors) = errors {
+        // The macro is an expression, so wrap the errors in a block.
+        let errors = errors.to_compile_error();
+        return quote! {{ #errors }}.into();
+    }
+
+    match graph {
+        Some(graph) => quote! {{
+            let __graph: &raug::graph::Graph = &#graph;
+            #(#body)*
+        }},
+        None => quote! {{
+            let __graph_owned = raug::graph::Graph::new();
+            let __graph = &__graph_owned;
+            #(#body)*
+            __graph_owned
+        }},
+    }
+    .into()
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use quote::{quote, ToTokens};
+use syn::visit_mut::VisitMut;
+
+use super::ResolveOutputs;
+
+/// Methods that are turned into math nodes, with their node name and number of arguments.
+const METHODS: &[(&str, &str, usize)] = &[
+    ("sin", "Sin", 0),
+    ("cos", "Cos", 0),
+    ("tan", "Tan", 0),
+    ("tanh", "Tanh", 0),
+    ("abs", "Abs", 0),
+    ("sqrt", "Sqrt", 0),
+    ("exp", "Exp", 0),
+    ("ln", "Ln", 0),
+    ("floor", "Floor", 0),
+    ("ceil", "Ceil", 0),
+    ("round", "Round", 0),
+    ("recip", "Recip", 0),
+    ("powf", "Pow", 1),
+    ("min", "Min", 1),
+    ("max", "Max", 1),
+    ("clamp", "Clamp", 2),
+];
+
+/// A lowered graph expression.
+enum Value {
+    /// An unsuffixed or `f32` literal, or an expression of literals folded at compile time.
+    Float(f32),
+    /// An unsuffixed integer literal, kept as written unless it's combined with a float or a
+    /// node output.
+    Int(f32, proc_macro2::TokenStream),
+    Bool(bool),
+    /// A Rust expression that doesn't depend on any node, like a variable.
+    Rust(proc_macro2::TokenStream),
+    /// An expression producing a node output.
+    Signal(proc_macro2::TokenStream),
+}
+
+impl ToTokens for Value {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Value::Float(value) if value.is_nan() => tokens.extend(quote! { f32::NAN }),
+            Value::Float(value) if *value == f32::INFINITY => {
+                tokens.extend(quote! { f32::INFINITY })
+            }
+            Value::Float(value) if *value == f32::NEG_INFINITY => {
+                tokens.extend(quote! { f32::NEG_INFINITY })
+            }
+            Value::Float(value) => {
+                proc_macro2::Literal::f32_suffixed(*value).to_tokens(tokens);
+            }
+            Value::Bool(value) => value.to_tokens(tokens),
+            Value::Int(_, expr) | Value::Rust(expr) | Value::Signal(expr) => expr.to_tokens(tokens),
+        }
+    }
+}
+
+impl Value {
+    /// Returns the value of a float or integer literal.
+    fn as_float(&self) -> Option<f32> {
+        match self {
+            Value::Float(value) | Value::Int(value, _) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Turns integer literals into floats, for use as math node inputs.
+    fn into_float(self) -> Value {
+        match self {
+            Value::Int(value, _) => Value::Float(value),
+            value => value,
+        }
+    }
+}
+
+/// Adds a `raug::builtins::math` node connected to `inputs` and returns its output.
+fn math_node(name: &str, inputs: &[Value]) -> Value {
+    let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+    let indices = 0..inputs.len();
+    Value::Signal(quote! {{
+        let __node = __graph.node(<raug::builtins::math::#name as Default>::default());
+        #(
+            if let Some(output) = raug::graph::node::IntoOutputOpt::into_output_opt(#inputs, __graph) {
+                __node.input(#indices).connect(output);
+            }
+        )*
+        __node.output(0)
+    }})
+}
+
+fn fold_binary(op: &syn::BinOp, a: f32, b: f32) -> Option<Value> {
+    Some(match op {
+        syn::BinOp::Add(_) => Value::Float(a + b),
+        syn::BinOp::Sub(_) => Value::Float(a - b),
+        syn::BinOp::Mul(_) => Value::Float(a * b),
+        syn::BinOp::Div(_) => Value::Float(a / b),
+        syn::BinOp::Rem(_) => Value::Float(a % b),
+        syn::BinOp::Lt(_) => Value::Bool(a < b),
+        syn::BinOp::Le(_) => Value::Bool(a <= b),
+        syn::BinOp::Gt(_) => Value::Bool(a > b),
+        syn::BinOp::Ge(_) => Value::Bool(a >= b),
+        syn::BinOp::Eq(_) => Value::Bool(a == b),
+        syn::BinOp::Ne(_) => Value::Bool(a != b),
+        _ => return None,
+    })
+}
+
+fn fold_method(method: &str, x: f32, args: &[f32]) -> f32 {
+    match (method, args) {
+        ("sin", []) => x.sin(),
+        ("cos", []) => x.cos(),
+        ("tan", []) => x.tan(),
+        ("tanh", []) => x.tanh(),
+        ("abs", []) => x.abs(),
+        ("sqrt", []) => x.sqrt(),
+        ("exp", []) => x.exp(),
+        ("ln", []) => x.ln(),
+        ("floor", []) => x.floor(),
+        ("ceil", []) => x.ceil(),
+        ("round", []) => x.round(),
+        ("recip", []) => x.recip(),
+        ("powf", [e]) => x.powf(*e),
+        ("min", [y]) => x.min(*y),
+        ("max", [y]) => x.max(*y),
+        // Like the `Clamp` node, this doesn't panic if the bounds are out of order.
+        ("clamp", [lo, hi]) => x.max(*lo).min(*hi),
+        _ => unreachable!(),
+    }
+}
+
+fn binary_node(op: &syn::BinOp) -> Option<&'static str> {
+    Some(match op {
+        syn::BinOp::Add(_) => "Add",
+        syn::BinOp::Sub(_) => "Sub",
+        syn::BinOp::Mul(_) => "Mul",
+        syn::BinOp::Div(_) => "Div",
+        syn::BinOp::Rem(_) => "Rem",
+        syn::BinOp::Lt(_) => "Lt",
+        syn::BinOp::Le(_) => "Le",
+        syn::BinOp::Gt(_) => "Gt",
+        syn::BinOp::Ge(_) => "Ge",
+        syn::BinOp::Eq(_) => "Eq",
+        syn::BinOp::Ne(_) => "Ne",
+        _ => return None,
+    })
+}
+
+/// Lowers graph expressions, turning operators and methods on node outputs into math nodes.
+pub struct Lower<'a> {
+    pub nodes: &'a HashMap<syn::Ident, syn::Path>,
+}
+
+impl Lower<'_> {
+    /// Returns an expression implementing `IntoOutputOpt` for the given graph expression.
+    pub fn lower(&self, expr: &syn::Expr) -> syn::Result<proc_macro2::TokenStream> {
+        Ok(self.value(expr)?.into_token_stream())
+    }
+
+    fn value(&self, expr: &syn::Expr) -> syn::Result<Value> {
+        match expr {
+            syn::Expr::Paren(syn::ExprParen { expr, .. })
+            | syn::Expr::Group(syn::ExprGroup { expr, .. }) => self.value(expr),
+            syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+                // Literals with another suffix, like `1.0f64`, are left as written.
+                syn::Lit::Float(lit) if matches!(lit.suffix(), "" | "f32") => {
+                    Ok(Value::Float(lit.base10_parse()?))
+                }
+                syn::Lit::Int(lit) if lit.suffix().is_empty() => {
+                    Ok(Value::Int(lit.base10_parse()?, lit.to_token_stream()))
+                }
+                syn::Lit::Bool(lit) => Ok(Value::Bool(lit.value)),
+                _ => Ok(Value::Rust(lit.to_token_stream())),
+            },
+            syn::Expr::Path(path)
+                if path
+                    .path
+                    .get_ident()
+                    .is_some_and(|name| self.nodes.contains_key(name)) =>
+            {
+                Ok(Value::Signal(path.to_token_stream()))
+            }
+            syn::Expr::Field(_) => {
+                let mut resolved = expr.clone();
+                ResolveOutputs { nodes: self.nodes }.visit_expr_mut(&mut resolved);
+                if matches!(&resolved, syn::Expr::MethodCall(call) if call.method == "output") {
+                    Ok(Value::Signal(resolved.into_token_stream()))
+                } else {
+                    Ok(Value::Rust(resolved.into_token_stream()))
+                }
+            }
+            syn::Expr::Unary(syn::ExprUnary {
+                op: syn::UnOp::Neg(_),
+                expr,
+                ..
+            }) => match self.value(expr)? {
+                Value::Float(value) => Ok(Value::Float(-value)),
+                Value::Int(value, expr) => Ok(Value::Int(-value, quote! { (-#expr) })),
+                Value::Signal(signal) => Ok(math_node("Neg", &[Value::Signal(signal)])),
+                value => Ok(Value::Rust(quote! { (-#value) })),
+            },
+            syn::Expr::Binary(syn::ExprBinary {
+                left, op, right, ..
+            }) => {
+                let (left, right) = (self.value(left)?, self.value(right)?);
+                // Integers are only folded with a float, so `2 * 3` stays an integer expression.
+                let has_float = matches!(left, Value::Float(_)) || matches!(right, Value::Float(_));
+                match (left.as_float(), right.as_float()) {
+                    (Some(a), Some(b)) if has_float => {
+                        if let Some(value) = fold_binary(op, a, b) {
+                            return Ok(value);
+                        }
+                    }
+                    _ => {}
+                }
+                match (&left, &right) {
+                    (Value::Signal(_), _) | (_, Value::Signal(_)) => {
+                        let Some(node) = binary_node(op) else {
+                            return Err(syn::Error::new_spanned(
+                                op,
+                                "Only `+`, `-`, `*`, `/`, `%` and comparisons can be used on node outputs",
+                            ));
+                        };
+                        return Ok(math_node(node, &[left.into_float(), right.into_float()]));
+                    }
+                    _ => {}
+                }
+                Ok(Value::Rust(quote! { (#left #op #right) }))
+            }
+            syn::Expr::MethodCall(call) => {
+                let receiver = self.value(&call.receiver)?;
+                let args = call
+                    .args
+                    .iter()
+                    .map(|arg| self.value(arg))
+                    .collect::<syn::Result<Vec<_>>>()?;
+                let is_signal = std::iter::once(&receiver)
+                    .chain(args.iter())
+                    .any(|value| matches!(value, Value::Signal(_)));
+                let is_float = matches!(receiver, Value::Float(_))
+                    && args.iter().all(|value| value.as_float().is_some());
+                let method = METHODS.iter().find(|(name, _, _)| call.method == name);
+                match method {
+                    Some((name, _, arity))
+                        if (is_signal || is_float) && *arity != args.len() =>
+                    {
+                        Err(syn::Error::new_spanned(
+                            &call.method,
+                            match arity {
+                                0 => format!("`{}` takes no arguments", name),
+                                1 => format!("`{}` takes 1 argument", name),
+                                _ => format!("`{}` takes {} arguments", name, arity),
+                            },
+                        ))
+                    }
+                    Some((name, _, _)) if is_float => {
+                        let floats = args
+                            .iter()
+                            .map(|value| value.as_float().unwrap())
+                            .collect::<Vec<_>>();
+                        let Value::Float(x) = receiver else {
+                            unreachable!()
+                        };
+                        Ok(Value::Float(fold_method(name, x, &floats)))
+                    }
+                    Some((_, node, _)) if is_signal => {
+                        let mut inputs = vec![receiver.into_float()];
+                        inputs.extend(args.into_iter().map(Value::into_float));
+                        Ok(math_node(node, &inputs))
+                    }
+                    None if is_signal => Err(syn::Error::new_spanned(
+                        &call.method,
+                        "Unsupported method on node outputs. Only `sin`, `cos`, `tan`, `tanh`, `abs`, `sqrt`, `exp`, `ln`, `floor`, `ceil`, `round`, `recip`, `powf`, `min`, `max` and `clamp` are supported.",
+                    )),
+                    _ => {
+                        let method = &call.method;
+                        let turbofish = &call.turbofish;
+                        Ok(Value::Rust(
+                            quote! { #receiver.#method #turbofish (#(#args),*) },
+                        ))
+                    }
+                }
+            }
+            _ => {
+                // Anything else is passed through as a Rust expression.
+                let mut resolved = expr.clone();
+                ResolveOutputs { nodes: self.nodes }.visit_expr_mut(&mut resolved);
+                Ok(Value::Rust(resolved.into_token_stream()))
+            }
+        }
+    }
+}
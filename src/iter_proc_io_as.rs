@@ -0,0 +1,413 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+
+/// A single input or output slot, optionally named with `name: Type`, and optionally followed
+/// by `default = expr` for inputs.
+struct Slot {
+    name: Option<syn::Ident>,
+    ty: syn::Type,
+    default: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for Slot {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = if input.peek(syn::Ident)
+            && input.peek2(syn::Token![:])
+            && !input.peek2(syn::Token![::])
+        {
+            let name = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let ty = input.parse()?;
+        let default = if input.peek(syn::Ident) {
+            let key = input.parse::<syn::Ident>()?;
+            if key != "default" {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "Expected `default = ...` or `,`",
+                ));
+            }
+            input.parse::<syn::Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { name, ty, default })
+    }
+}
+
+struct IterProcIoAs {
+    inputs: syn::Ident,
+    input_slots: Punctuated<Slot, syn::Token![,]>,
+    outputs: syn::Ident,
+    output_slots: Punctuated<Slot, syn::Token![,]>,
+    chunks: Option<usize>,
+}
+
+impl syn::parse::Parse for IterProcIoAs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let inputs = input.parse()?;
+        input.parse::<syn::Token![as]>()?;
+        let input_slots;
+        syn::bracketed!(input_slots in input);
+        let input_slots = input_slots.parse_terminated(Slot::parse, syn::Token![,])?;
+        input.parse::<syn::Token![,]>()?;
+        let outputs = input.parse()?;
+        input.parse::<syn::Token![as]>()?;
+        let output_slots;
+        syn::bracketed!(output_slots in input);
+        let output_slots = output_slots.parse_terminated(Slot::parse, syn::Token![,])?;
+        let mut chunks = None;
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let key = input.parse::<syn::Ident>()?;
+            if key != "chunks" {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "Unknown option. Only `chunks` is supported.",
+                ));
+            }
+            input.parse::<syn::Token![=]>()?;
+            let size = input.parse::<syn::LitInt>()?;
+            match size.base10_parse::<usize>()? {
+                0 => {
+                    return Err(syn::Error::new_spanned(
+                        size,
+                        "Expected a positive chunk size",
+                    ))
+                }
+                size => chunks = Some(size),
+            }
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+        Ok(Self {
+            inputs,
+            outputs,
+            input_slots,
+            output_slots,
+            chunks,
+        })
+    }
+}
+
+/// Returns `T` if the type is `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.first() {
+                Some(syn::GenericArgument::Type(inner)) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the type is `Any`, or a path ending in `Any` like `raug::signal::Any`.
+fn is_any(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Any" && segment.arguments.is_empty()),
+        syn::Type::Group(group) => is_any(&group.elem),
+        _ => false,
+    }
+}
+
+pub fn iter_proc_io_as(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as IterProcIoAs);
+
+    let inputs = input.inputs;
+    let outputs = input.outputs;
+
+    let slots = input
+        .input_slots
+        .iter()
+        .chain(input.output_slots.iter())
+        .collect::<Vec<_>>();
+    let named = slots.iter().filter(|slot| slot.name.is_some()).count();
+    if named != 0 && named != slots.len() {
+        let unnamed = slots.iter().find(|slot| slot.name.is_none()).unwrap();
+        return syn::Error::new_spanned(
+            &unnamed.ty,
+            "Expected a name for this slot, since other slots are named (e.g. `freq: f32`)",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let names = slots
+        .iter()
+        .filter_map(|slot| slot.name.as_ref())
+        .collect::<Vec<_>>();
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            return syn::Error::new_spanned(name, format!("Duplicate slot name `{}`", name))
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    for slot in input.output_slots.iter() {
+        if let Some(default) = &slot.default {
+            return syn::Error::new_spanned(default, "Only inputs can have a default")
+                .to_compile_error()
+                .into();
+        }
+        if option_inner(&slot.ty).is_some() {
+            return syn::Error::new_spanned(&slot.ty, "Outputs cannot be optional")
+                .to_compile_error()
+                .into();
+        }
+    }
+    for slot in input.input_slots.iter() {
+        if let (Some(default), true) = (&slot.default, input.chunks.is_some()) {
+            return syn::Error::new_spanned(default, "`default` cannot be combined with `chunks`")
+                .to_compile_error()
+                .into();
+        }
+        if let (Some(_), true) = (option_inner(&slot.ty), input.chunks.is_some()) {
+            return syn::Error::new_spanned(
+                &slot.ty,
+                "`Option` slots cannot be iterated in chunks",
+            )
+            .to_compile_error()
+            .into();
+        }
+        if let (Some(default), true) = (&slot.default, is_any(&slot.ty)) {
+            return syn::Error::new_spanned(default, "`Any` slots cannot have a default")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    if input.chunks.is_some() {
+        if let Some(slot) = slots.iter().find(|slot| is_any(&slot.ty)) {
+            return syn::Error::new_spanned(&slot.ty, "`Any` slots cannot be iterated in chunks")
+                .to_compile_error()
+                .into();
+        }
+    }
+
+    let input_count = input.input_slots.len();
+    let output_count = input.output_slots.len();
+
+    let mut input_idents = vec![];
+    for i in 0..input_count {
+        let ident = syn::Ident::new(&format!("in{}", i), proc_macro2::Span::call_site());
+        input_idents.push(ident);
+    }
+
+    let mut output_idents = vec![];
+    for i in 0..output_count {
+        let ident = syn::Ident::new(&format!("out{}", i), proc_macro2::Span::call_site());
+        output_idents.push(ident);
+    }
+
+    let slices_inputs = input
+        .input_slots
+        .iter()
+        .any(|slot| slot.default.is_some() || option_inner(&slot.ty).is_some());
+    let block_size = if slices_inputs || input.chunks.is_some() {
+        quote! { let block_size = #inputs.block_size(); }
+    } else {
+        quote! {}
+    };
+
+    let start = quote! {
+        #block_size
+
+        let raug::processor::io::ProcessorInputs {
+            input_specs,
+            inputs,
+            env,
+            ..
+        } = #inputs;
+
+        let [#(#input_idents),*] = inputs else {
+            return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                "Expected {} inputs, got {}",
+                #input_count,
+                inputs.len(),
+            )));
+        };
+
+        let raug::processor::io::ProcessorOutputs {
+            output_spec,
+            outputs,
+            mode,
+            ..
+        } = #outputs;
+
+        let [#(#output_idents),*] = outputs else {
+            return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                "Expected {} outputs, got {}",
+                #output_count,
+                outputs.len(),
+            )));
+        };
+    };
+
+    let mut chunks = vec![];
+
+    for (i, (input_ident, slot)) in input_idents
+        .iter()
+        .zip(input.input_slots.iter())
+        .enumerate()
+    {
+        // `Option<T>` slots are checked as `T`, and yield owned `Option<T>` values.
+        let optional = option_inner(&slot.ty);
+        let input_typ = optional.unwrap_or(&slot.ty);
+        if is_any(input_typ) {
+            let chunk = quote! {
+                raug::processor::io::ProcessorInputs::new(
+                    std::slice::from_ref(&input_specs[#i]),
+                    std::slice::from_ref(#input_ident),
+                    env,
+                ).iter_input(0)
+            };
+            chunks.push(chunk);
+            continue;
+        }
+        let iter_input = quote! {
+            raug::processor::io::ProcessorInputs::new(
+                std::slice::from_ref(&input_specs[#i]),
+                std::slice::from_ref(#input_ident),
+                env,
+            )
+        };
+        // Type check the input the same way as per-sample iteration, then slice it.
+        let input_slice = quote! {
+            let _ = #iter_input.iter_input_as::<#input_typ>(0)?;
+            let input = match #iter_input.input_as::<#input_typ>(0) {
+                Some(input) if input.len() < block_size => {
+                    return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                        "Expected input {} to have at least {} samples, got {}",
+                        #i,
+                        block_size,
+                        input.len(),
+                    )));
+                }
+                input => input,
+            };
+        };
+        let chunk = if let Some(size) = input.chunks {
+            quote! {{
+                #input_slice
+                (0..block_size.div_ceil(#size)).map(move |chunk| {
+                    input.map(|input| &input[chunk * #size..block_size.min((chunk + 1) * #size)])
+                })
+            }}
+        } else if let Some(default) = &slot.default {
+            quote! {{
+                #input_slice
+                let default: #input_typ = #default;
+                (0..block_size).map(move |i| {
+                    input.map_or_else(|| Clone::clone(&default), |input| Clone::clone(&input[i]))
+                })
+            }}
+        } else if optional.is_some() {
+            quote! {{
+                #input_slice
+                (0..block_size).map(move |i| input.map(|input| Clone::clone(&input[i])))
+            }}
+        } else {
+            quote! {
+                #iter_input.iter_input_as::<#input_typ>(0)?
+            }
+        };
+        chunks.push(chunk);
+    }
+
+    for (i, (output_ident, slot)) in output_idents
+        .iter()
+        .zip(input.output_slots.iter())
+        .enumerate()
+    {
+        let output_typ = &slot.ty;
+        if is_any(output_typ) {
+            let chunk = quote! {
+                raug::processor::io::ProcessorOutputs::new(
+                    std::slice::from_ref(&output_spec[#i]),
+                    std::slice::from_mut(#output_ident),
+                    mode,
+                ).iter_output_mut(0)
+            };
+            chunks.push(chunk);
+            continue;
+        }
+        let iter_output = quote! {
+            raug::processor::io::ProcessorOutputs::new(
+                std::slice::from_ref(&output_spec[#i]),
+                std::slice::from_mut(#output_ident),
+                mode,
+            )
+        };
+        let chunk = if let Some(size) = input.chunks {
+            // Type check the output the same way as per-sample iteration, then slice it.
+            quote! {{
+                let _ = #iter_output.iter_output_mut_as::<#output_typ>(0)?;
+                let len = #output_ident.len();
+                match #output_ident
+                    .as_mut_slice::<#output_typ>()
+                    .and_then(|output| output.get_mut(..block_size))
+                {
+                    Some(output) => output.chunks_mut(#size),
+                    None => {
+                        return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                            "Expected output {} to have at least {} samples, got {}",
+                            #i,
+                            block_size,
+                            len,
+                        )));
+                    }
+                }
+            }}
+        } else {
+            quote! {
+                #iter_output.iter_output_mut_as::<#output_typ>(0)?
+            }
+        };
+        chunks.push(chunk);
+    }
+
+    // Named slots yield a struct with a field per slot instead of a tuple.
+    let items = if names.is_empty() {
+        quote! {}
+    } else {
+        let params = (0..names.len())
+            .map(|i| format_ident!("T{}", i))
+            .collect::<Vec<_>>();
+        let pattern = match names.as_slice() {
+            [name] => quote! { #name },
+            names => quote! { (#(#names),*) },
+        };
+        quote! {
+            .map(|#pattern| {
+                struct IterProcIo<#(#params),*> {
+                    #(#names: #params,)*
+                }
+                IterProcIo { #(#names),* }
+            })
+        }
+    };
+
+    let output = quote! {{
+        #start
+
+        raug::__itertools::izip!(#(#chunks),*)#items
+    }};
+
+    output.into()
+}
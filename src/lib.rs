@@ -1,15 +1,277 @@
 use proc_macro::TokenStream;
-use processor_attribute::processor_attribute;
+use processor_attribute::{processor_attribute, subgraph_attribute};
 use quote::quote;
-use syn::punctuated::Punctuated;
 
+mod bench_processor;
+mod graph;
+mod iter_proc_io_as;
 mod processor_attribute;
+mod processor_registry;
+mod processor_snapshot;
+mod processor_test;
 
+/// Turns a per-sample function into a processor struct implementing `raug::processor::Processor`.
+///
+/// Each argument is annotated with one of:
+///
+/// - `#[state]`: a `&mut T` kept in a struct field between samples.
+/// - `#[input]`: a `&T` or `T` read from an input port, holding its last value when unconnected.
+/// - `#[output]`: a `&mut T` written to an output port. The return value is also written to
+///   output ports, named `out` (or `out0`, `out1`, ... for tuples) unless given with `outputs(...)`.
+/// - `#[sample_index]`: the index of the sample within the current block, which restarts at zero
+///   every block.
+/// - `#[time]`: the time in seconds since the processor was allocated.
+/// - `#[block_size]`: the size of the current block.
+///
+/// A `ProcEnv` argument receives the processing environment.
 #[proc_macro_attribute]
 pub fn processor(attr: TokenStream, item: TokenStream) -> TokenStream {
     processor_attribute(attr, item)
 }
 
+/// Turns a function that builds nodes on a graph into a reusable subgraph.
+///
+/// The function takes the graph to build on, plus `#[input]` and `#[output]` arguments declared
+/// like those of `#[processor]`. In the body, each input is a `raug::graph::node::Output` to read
+/// from, and each output is a `raug::graph::node::Input` to connect to.
+///
+/// This generates a struct with a public field per input, holding the value used when the input
+/// is left unconnected, and the same `input_spec`, `output_spec` and `node` methods as a processor.
+/// Calling `node` adds the subgraph's nodes to the graph and returns a node with the subgraph's
+/// outputs. Subgraphs can also be used in `graph!`.
+///
+/// The `derive(...)` and `attr(...)` options apply to the struct, as for `#[processor]`.
+///
+/// # Examples
+///
+/// ```
+/// use raug::graph::Graph;
+/// use raug_macros::{processor, subgraph};
+///
+/// #[processor(derive(Default))]
+/// pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+///     x * g
+/// }
+///
+/// #[subgraph(derive(Default))]
+/// pub fn stereo_gain(
+///     graph: &Graph,
+///     #[input] left: &f32,
+///     #[input] right: &f32,
+///     #[input] g: &f32,
+///     #[output] left_out: &mut f32,
+///     #[output] right_out: &mut f32,
+/// ) {
+///     left_out.connect(Gain::default().node(graph, left, g).output(0));
+///     right_out.connect(Gain::default().node(graph, right, g).output(0));
+/// }
+///
+/// fn main() {
+///     let graph = Graph::new();
+///     let stereo = StereoGain { g: 0.5, ..Default::default() }.node(&graph, 1.0, -1.0, ());
+///     assert_eq!(StereoGain::default().output_spec().len(), 2);
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn subgraph(attr: TokenStream, item: TokenStream) -> TokenStream {
+    subgraph_attribute(attr, item)
+}
+
+/// Generates the processor registry for the current crate.
+///
+/// This must be invoked once at the crate root. It defines a `ProcessorRegistration` type and a
+/// `ProcessorRegistry` that collects every processor annotated with `#[processor(register)]`,
+/// so processors can be looked up and constructed by name. Registered processors must implement
+/// `Default`.
+///
+/// Registrations are collected with the [`inventory`](https://docs.rs/inventory) crate, which the
+/// invoking crate must depend on. If it's reachable under another path, pass it with
+/// `processor_registry!(inventory = path::to::inventory)`.
+///
+/// # Examples
+///
+/// ```
+/// use raug_macros::{processor, processor_registry};
+///
+/// processor_registry!();
+///
+/// #[processor(register, derive(Default))]
+/// pub fn gain(#[input] x: &f32, #[input] g: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+///     *out = x * g;
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let registration = ProcessorRegistry::get("Gain").unwrap();
+///     assert_eq!((registration.input_spec)().len(), 2);
+///     assert!(ProcessorRegistry::create("Gain").is_some());
+/// }
+/// ```
+#[proc_macro]
+pub fn processor_registry(input: TokenStream) -> TokenStream {
+    processor_registry::processor_registry(input)
+}
+
+/// Renders a processor offline and returns its outputs, without building a graph.
+///
+/// The first argument is an expression creating the processor. The remaining options are:
+///
+/// - `inputs = [a, _, ...]`: one array, `Vec` or slice of samples per input, or `_` to leave an
+///   input unconnected.
+/// - `outputs = [T, ...]`: the output types, returning a tuple of `Vec<T>`s. Without this, every
+///   output is read as `f32` and a `Vec<Vec<f32>>` is returned.
+/// - `sample_rate` (default `48_000.0`) and `block_size` (default `64`).
+/// - `frames`: the number of samples to render, which defaults to the length of the longest input.
+///
+/// The processor is allocated, then processed block by block. The macro evaluates to a
+/// `ProcResult` containing the rendered outputs.
+///
+/// # Examples
+///
+/// ```
+/// use raug_macros::{processor, processor_test};
+///
+/// #[processor(derive(Default))]
+/// pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+///     x * g
+/// }
+///
+/// fn main() {
+///     let outputs = processor_test!(
+///         Gain::default(),
+///         inputs = [[1.0, 2.0, 3.0], [0.5; 3]],
+///         block_size = 2,
+///     )
+///     .unwrap();
+///     assert_eq!(outputs, [[0.5, 1.0, 1.5]]);
+/// }
+/// ```
+#[proc_macro]
+pub fn processor_test(input: TokenStream) -> TokenStream {
+    processor_test::processor_test(input)
+}
+
+/// Turns a function returning a processor into a golden-file snapshot test.
+///
+/// The processor is rendered offline and its `f32` outputs are compared against a CSV snapshot,
+/// with a header row of output names and a row per sample. When the `UPDATE_SNAPSHOTS` environment
+/// variable is set, the snapshot is written instead. Otherwise the test fails if the snapshot is
+/// missing, or at the first sample that differs by more than the tolerance, naming the output port.
+///
+/// Options:
+///
+/// - `input = "path.csv"`: a CSV file with a header row of input names and a row per sample.
+///   Inputs not in the file are left unconnected.
+/// - `frames = N`: the number of samples to render, which defaults to the length of the input.
+/// - `snapshot = "path.csv"`: defaults to `tests/snapshots/<function name>.csv`.
+/// - `tolerance` (default `1e-6`), `sample_rate` (default `48_000.0`) and `block_size`
+///   (default `512`).
+///
+/// Paths are relative to the crate's manifest directory.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[processor_snapshot(input = "tests/inputs/impulse.csv", frames = 48000)]
+/// fn one_pole_impulse() -> OnePole {
+///     OnePole { coeff: 0.1, ..Default::default() }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn processor_snapshot(attr: TokenStream, item: TokenStream) -> TokenStream {
+    processor_snapshot::processor_snapshot(attr, item)
+}
+
+/// Generates a criterion benchmark function for a processor.
+///
+/// The expansion is a `fn bench_<type>(c: &mut criterion::Criterion)` that processes a
+/// preallocated block in a `process` benchmark, and runs a per-sample closure over the same block
+/// in a `scalar baseline` benchmark. Both report their throughput in samples per second. Every
+/// `f32` input is driven by a 440 Hz sine; other inputs are left unconnected.
+///
+/// The first argument is the processor type. The remaining options are:
+///
+/// - `block_size` (default `512`) and `sample_rate` (default `48_000.0`).
+/// - `processor = expr`: the processor to benchmark, which defaults to `Type::default()`.
+/// - `baseline = |x: f32| ...`: the scalar baseline, which defaults to copying the input.
+/// - `name = ident`: the name of the generated function.
+/// - `criterion = path`: the path to the criterion crate, which defaults to `criterion`.
+///
+/// # Examples
+///
+/// ```ignore
+/// use raug_macros::bench_processor;
+///
+/// bench_processor!(Gain, block_size = 512, baseline = |x: f32| x * 0.5);
+///
+/// criterion::criterion_group!(benches, bench_gain);
+/// criterion::criterion_main!(benches);
+/// ```
+#[proc_macro]
+pub fn bench_processor(input: TokenStream) -> TokenStream {
+    bench_processor::bench_processor(input)
+}
+
+/// Builds a `raug::graph::Graph` from a list of node definitions and connections.
+///
+/// Each statement is one of:
+///
+/// - `name = Type(args);`: adds a `Type::default()` node. Arguments are connected to inputs by
+///   position, or by name with `input: value`. Positional arguments come first, and each input
+///   can only be connected once.
+/// - `name.input <- value;`: connects a value to an input of an existing node, e.g. for feedback.
+/// - `dac <- value, ...;`: adds an audio output for each value and connects it.
+///
+/// Values are anything implementing `IntoOutputOpt`, and `node.output` refers to an output of a
+/// node defined earlier. Port names are resolved against the names generated by `#[processor]`,
+/// so an unknown port is a compile error.
+///
+/// Operators and methods on node outputs add the corresponding `raug::builtins::math` nodes:
+///
+/// - `+`, `-`, `*`, `/` and `%` add `Add`, `Sub`, `Mul`, `Div` and `Rem` nodes, and unary `-`
+///   adds a `Neg` node.
+/// - `<`, `<=`, `>`, `>=`, `==` and `!=` add `Lt`, `Le`, `Gt`, `Ge`, `Eq` and `Ne` nodes.
+/// - `sin`, `cos`, `tan`, `tanh`, `abs`, `sqrt`, `exp`, `ln`, `floor`, `ceil`, `round`, `recip`,
+///   `powf`, `min`, `max` and `clamp` add the node with the same name in upper camel case, except
+///   `powf`, which adds a `Pow` node.
+///
+/// Subexpressions of literals are folded at compile time, so `osc.out * (2.0 * 0.25)` adds a
+/// single `Mul` node. Only unsuffixed and `f32` float literals are folded. Unsuffixed integer
+/// literals are treated as `f32` when combined with a float or a node output, and other literals
+/// are left as written. Expressions that don't involve node outputs, like
+/// `gain * 0.5`, are evaluated as regular Rust expressions.
+///
+/// The macro evaluates to the new graph. To add the nodes to an existing graph instead, start
+/// with `graph =>`.
+///
+/// # Examples
+///
+/// ```
+/// use raug_macros::{graph, processor};
+///
+/// #[processor(derive(Default))]
+/// pub fn sine_osc(#[input] freq: &f32) -> f32 {
+///     freq.sin()
+/// }
+///
+/// #[processor(derive(Default))]
+/// pub fn lowpass(#[input] x: &f32, #[input] cutoff: &f32) -> f32 {
+///     x * cutoff
+/// }
+///
+/// fn main() {
+///     let graph = graph! {
+///         osc = SineOsc(freq: 440.0);
+///         flt = Lowpass(osc.out, cutoff: 1000.0);
+///         dac <- flt.out * 0.5;
+///     };
+/// }
+/// ```
+#[proc_macro]
+pub fn graph(input: TokenStream) -> TokenStream {
+    graph::graph(input)
+}
+
 /// Returns the MIDI note constant for the given note name and octave.
 ///
 /// # Examples
@@ -114,144 +376,36 @@ fn parse_note(input: &str) -> u8 {
     note as u8
 }
 
-struct IterProcIoAs {
-    inputs: syn::Ident,
-    input_types: Punctuated<syn::Type, syn::Token![,]>,
-    outputs: syn::Ident,
-    output_types: Punctuated<syn::Type, syn::Token![,]>,
-}
-
-impl syn::parse::Parse for IterProcIoAs {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let inputs = input.parse()?;
-        input.parse::<syn::Token![as]>()?;
-        let input_types;
-        syn::bracketed!(input_types in input);
-        let input_types = input_types.parse_terminated(syn::Type::parse, syn::Token![,])?;
-        input.parse::<syn::Token![,]>()?;
-        let outputs = input.parse()?;
-        input.parse::<syn::Token![as]>()?;
-        let output_types;
-        syn::bracketed!(output_types in input);
-        let output_types = output_types.parse_terminated(syn::Type::parse, syn::Token![,])?;
-        Ok(Self {
-            inputs,
-            outputs,
-            input_types,
-            output_types,
-        })
-    }
-}
-
+/// Iterates over the inputs and outputs of a processor sample by sample, checking their types.
+///
+/// Each slot is either a bare type, yielding a tuple of the slots in order, or named like
+/// `freq: f32`, yielding a struct with a field per slot. Inputs yield `Option<&T>` and outputs
+/// yield `&mut T`. `Any` (or a path ending in `Any`) iterates over the untyped buffers instead.
+/// An input slot typed `Option<T>` yields owned `Option<T>` values, cloned from the input, and an
+/// input slot followed by `default = expr` yields `T` by value, substituting `expr` when
+/// unconnected.
+///
+/// The enclosing function must return a `ProcResult`, since a mismatched number of inputs or
+/// outputs returns an error.
+///
+/// With a trailing `chunks = N`, each item instead holds aligned sub-slices of up to `N` samples:
+/// `Option<&[T]>` for inputs and `&mut [T]` for outputs. The last chunk is shorter if the block
+/// size isn't a multiple of `N`.
+///
+/// # Examples
+///
+/// ```
+/// use raug::processor::{io::*, ProcResult};
+/// use raug_macros::iter_proc_io_as;
+///
+/// fn process(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+///     for frame in iter_proc_io_as!(inputs as [freq: f32, amp: f32], outputs as [out: f32]) {
+///         *frame.out = frame.freq.copied().unwrap_or(440.0) * frame.amp.copied().unwrap_or(1.0);
+///     }
+///     Ok(())
+/// }
+/// ```
 #[proc_macro]
 pub fn iter_proc_io_as(input: TokenStream) -> TokenStream {
-    let input = syn::parse_macro_input!(input as IterProcIoAs);
-
-    let inputs = input.inputs;
-    let outputs = input.outputs;
-
-    let input_count = input.input_types.len();
-    let output_count = input.output_types.len();
-
-    let mut input_idents = vec![];
-    for i in 0..input_count {
-        let ident = syn::Ident::new(&format!("in{}", i), proc_macro2::Span::call_site());
-        input_idents.push(ident);
-    }
-
-    let mut output_idents = vec![];
-    for i in 0..output_count {
-        let ident = syn::Ident::new(&format!("out{}", i), proc_macro2::Span::call_site());
-        output_idents.push(ident);
-    }
-
-    let start = quote! {
-        let raug::processor::io::ProcessorInputs {
-            input_specs,
-            inputs,
-            env,
-            ..
-        } = #inputs;
-
-        let [#(#input_idents),*] = inputs else {
-            panic!("Expected {} inputs, got {}", #input_count, inputs.len());
-        };
-
-        let raug::processor::io::ProcessorOutputs {
-            output_spec,
-            outputs,
-            mode,
-            ..
-        } = #outputs;
-
-        let [#(#output_idents),*] = outputs else {
-            panic!("Expected {} outputs, got {}", #output_count, outputs.len());
-        };
-    };
-
-    let mut chunks = vec![];
-
-    for (i, (input_ident, input_typ)) in input_idents
-        .iter()
-        .zip(input.input_types.iter())
-        .enumerate()
-    {
-        if let syn::Type::Path(path) = input_typ {
-            if path.path.get_ident().unwrap() == "Any" {
-                let chunk = quote! {
-                    raug::processor::io::ProcessorInputs::new(
-                        std::slice::from_ref(&input_specs[#i]),
-                        std::slice::from_ref(#input_ident),
-                        env,
-                    ).iter_input(0)
-                };
-                chunks.push(chunk);
-                continue;
-            }
-        }
-        let chunk = quote! {
-            raug::processor::io::ProcessorInputs::new(
-                std::slice::from_ref(&input_specs[#i]),
-                std::slice::from_ref(#input_ident),
-                env,
-            ).iter_input_as::<#input_typ>(0)?
-        };
-        chunks.push(chunk);
-    }
-
-    for (i, (output_ident, output_typ)) in output_idents
-        .iter()
-        .zip(input.output_types.iter())
-        .enumerate()
-    {
-        if let syn::Type::Path(path) = output_typ {
-            if path.path.get_ident().unwrap() == "Any" {
-                let chunk = quote! {
-                    raug::processor::io::ProcessorOutputs::new(
-                        std::slice::from_ref(&output_spec[#i]),
-                        std::slice::from_mut(#output_ident),
-                        mode,
-                    ).iter_output_mut(0)
-                };
-                chunks.push(chunk);
-                continue;
-            }
-        }
-        let chunk = quote! {
-            raug::processor::io::ProcessorOutputs::new(
-                std::slice::from_ref(&output_spec[#i]),
-                std::slice::from_mut(#output_ident),
-                mode,
-            ).iter_output_mut_as::<#output_typ>(0)?
-        };
-        chunks.push(chunk);
-    }
-
-    let output = quote! {{
-        #start
-
-        raug::__itertools::izip!(#(#chunks),*)
-    }};
-
-    output.into()
+    iter_proc_io_as::iter_proc_io_as(input)
 }
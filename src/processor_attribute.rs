@@ -1,11 +1,91 @@
 use convert_case::{Case, Casing};
 use proc_macro::TokenStream;
-use quote::{format_ident, quote, ToTokens};
-use syn::{parse::Parser, parse_macro_input, punctuated::Punctuated};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{parse::Parser, parse_macro_input, punctuated::Punctuated, spanned::Spanned};
+
+use buffers::{check_input_count, get_input, output_slot, split_outputs};
+use channels::{channels_processor, ChannelsArgs, ChannelsOptions};
+use fuzz::fuzz_impl;
+use guard::{flush_denormals, guard_output, parse_guard_nan};
+use oversample::{check_oversample_ports, halfband_impl};
+use poly::{parse_poly_outputs, parse_voice_stealing, poly_processor, PolyOptions};
+use realtime::check_realtime;
+pub use subgraph::subgraph_attribute;
+
+mod buffers;
+mod channels;
+mod fuzz;
+mod guard;
+mod oversample;
+mod poly;
+mod realtime;
+mod subgraph;
+
+const ARG_ATTRS: &[&str] = &[
+    "state",
+    "input",
+    "output",
+    "sample_index",
+    "time",
+    "block_size",
+];
+
+/// How an option of `#[processor(...)]` is written.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ArgKind {
+    Flag,
+    List,
+    KeyValue,
+}
+
+impl ArgKind {
+    fn describe(self) -> &'static str {
+        match self {
+            ArgKind::Flag => "flag",
+            ArgKind::List => "list",
+            ArgKind::KeyValue => "key-value pair",
+        }
+    }
+
+    fn example(self, name: &str) -> String {
+        match self {
+            ArgKind::Flag => format!("`{}`", name),
+            ArgKind::List => format!("`{}(...)`", name),
+            ArgKind::KeyValue => format!("`{} = ...`", name),
+        }
+    }
+}
+
+/// Every option of `#[processor(...)]`, used to generate the errors for unknown options.
+const PROCESSOR_ARGS: &[(&str, ArgKind)] = &[
+    ("register", ArgKind::Flag),
+    ("derive", ArgKind::List),
+    ("attr", ArgKind::List),
+    ("outputs", ArgKind::List),
+    ("allocate", ArgKind::KeyValue),
+    ("resize_buffers", ArgKind::KeyValue),
+    ("oversample", ArgKind::KeyValue),
+    ("poly", ArgKind::KeyValue),
+    ("voice_stealing", ArgKind::KeyValue),
+    ("poly_outputs", ArgKind::KeyValue),
+    ("channels", ArgKind::KeyValue),
+    ("guard_nan", ArgKind::KeyValue),
+    ("bypass", ArgKind::Flag),
+    ("mix", ArgKind::Flag),
+    ("dry", ArgKind::KeyValue),
+    ("wet", ArgKind::KeyValue),
+    ("flush_denormals", ArgKind::Flag),
+    ("realtime", ArgKind::Flag),
+    ("fuzz", ArgKind::Flag),
+];
 
 struct ProcessorArg {
     name: syn::Ident,
+    /// The pattern the argument is bound to in the processor function.
+    pat: syn::Pat,
     ty: syn::Type,
+    /// Whether the argument is passed by value instead of by reference.
+    by_value: bool,
 }
 
 impl ToTokens for ProcessorArg {
@@ -17,75 +97,542 @@ impl ToTokens for ProcessorArg {
     }
 }
 
+fn push_error(errors: &mut Option<syn::Error>, error: syn::Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
+    }
+}
+
+/// Returns the error for an option of `#[processor(...)]` that isn't known as the given kind,
+/// listing the options of that kind and suggesting the closest one.
+fn unknown_processor_arg(path: &syn::Path, kind: ArgKind) -> syn::Error {
+    let name = path.get_ident().map(|ident| ident.to_string());
+    if let Some((name, other)) = PROCESSOR_ARGS
+        .iter()
+        .find(|(arg, _)| Some(*arg) == name.as_deref())
+    {
+        return syn::Error::new_spanned(
+            path.clone(),
+            format!(
+                "`{}` is a {}, so it must be written as {}",
+                name,
+                other.describe(),
+                other.example(name)
+            ),
+        );
+    }
+    let candidates = PROCESSOR_ARGS
+        .iter()
+        .filter(|(_, other)| *other == kind)
+        .map(|(arg, _)| *arg)
+        .collect::<Vec<_>>();
+    let list = match candidates.split_last() {
+        Some((last, [])) => format!("`{}`", last),
+        Some((last, rest)) => format!(
+            "{} and `{}`",
+            rest.iter()
+                .map(|arg| format!("`{}`", arg))
+                .collect::<Vec<_>>()
+                .join(", "),
+            last
+        ),
+        None => String::new(),
+    };
+    let mut message = format!(
+        "Unknown attribute. Only {} are supported as {}s.",
+        list,
+        kind.describe()
+    );
+    if let Some(suggestion) = name.and_then(|name| suggest(&name, &candidates)) {
+        message.push_str(&format!(" Did you mean `{}`?", suggestion));
+    }
+    syn::Error::new_spanned(path.clone(), message)
+}
+
+/// Parses the `derive(...)` and `attr(...)` options applied to the generated struct, shared by
+/// `#[processor]` and `#[subgraph]`. Returns whether `meta_list` was one of them.
+fn parse_struct_option(
+    meta_list: &syn::MetaList,
+    derives: &mut Vec<syn::Path>,
+    attrs: &mut Vec<syn::Meta>,
+    errors: &mut Option<syn::Error>,
+) -> bool {
+    if meta_list.path.is_ident("derive") {
+        match meta_list.parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated) {
+            Ok(paths) => derives.extend(paths),
+            Err(err) => push_error(errors, err),
+        }
+    } else if meta_list.path.is_ident("attr") {
+        match meta_list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated) {
+            Ok(metas) => attrs.extend(metas),
+            Err(err) => push_error(errors, err),
+        }
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Returns the candidate closest to `name`, if any is within a small edit distance.
+fn suggest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(name, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(candidate.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let cost = if a == *b { 0 } else { 1 };
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Returns the field/port name of an argument, either from `#[kind(name = ...)]` or from its identifier pattern.
+///
+/// If `shared` is given, the `shared` flag is also accepted and stored in it.
+fn arg_name(
+    arg: &syn::PatType,
+    attr: &syn::Attribute,
+    kind: &str,
+    mut shared: Option<&mut Option<syn::Path>>,
+) -> syn::Result<syn::Ident> {
+    let attr_name = kind.to_lowercase();
+    let mut name = None;
+    if let syn::Meta::List(_) = &attr.meta {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<syn::Ident>()?);
+                Ok(())
+            } else if let (true, Some(shared)) = (meta.path.is_ident("shared"), &mut shared) {
+                **shared = Some(meta.path.clone());
+                Ok(())
+            } else if shared.is_some() {
+                Err(meta.error(format!(
+                    "Unknown argument for `#[{}]`. Only `name` and `shared` are supported.",
+                    attr_name
+                )))
+            } else {
+                Err(meta.error(format!(
+                    "Unknown argument for `#[{}]`. Only `name` is supported.",
+                    attr_name
+                )))
+            }
+        })?;
+    }
+    if let Some(name) = name {
+        return Ok(name);
+    }
+    if let syn::Pat::Ident(pat_ident) = &*arg.pat {
+        Ok(pat_ident.ident.clone())
+    } else {
+        Err(syn::Error::new_spanned(
+            arg.pat.clone(),
+            format!(
+                "{} argument must be a named identifier, or specify a name with `#[{}(name = ...)]`",
+                kind, attr_name
+            ),
+        ))
+    }
+}
+
+/// Returns whether the given path is `ProcEnv` or `raug::processor::io::ProcEnv`.
+fn is_proc_env(path: &syn::Path) -> bool {
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>();
+    segments == ["ProcEnv"] || segments == ["raug", "processor", "io", "ProcEnv"]
+}
+
+fn is_f32(ty: &syn::Type) -> bool {
+    matches!(ungroup(ty), syn::Type::Path(type_path) if type_path.path.is_ident("f32"))
+}
+
+fn ungroup(ty: &syn::Type) -> &syn::Type {
+    if let syn::Type::Group(group) = ty {
+        &group.elem
+    } else {
+        ty
+    }
+}
+
+fn is_reference(ty: &syn::Type) -> bool {
+    matches!(ungroup(ty), syn::Type::Reference(_))
+}
+
+/// Returns the referenced type of an argument, checking that the reference has the expected mutability.
+fn reference_elem(arg: &syn::PatType, kind: &str, mutable: bool) -> syn::Result<syn::Type> {
+    if let syn::Type::Reference(reference) = ungroup(&arg.ty) {
+        if mutable && reference.mutability.is_none() {
+            return Err(syn::Error::new_spanned(
+                reference.clone(),
+                format!("{} argument must be a mutable reference", kind),
+            ));
+        }
+        if !mutable && reference.mutability.is_some() {
+            return Err(syn::Error::new_spanned(
+                reference.clone(),
+                format!("{} argument must be an immutable reference", kind),
+            ));
+        }
+        Ok(*reference.elem.clone())
+    } else {
+        Err(syn::Error::new_spanned(
+            arg.ty.clone(),
+            format!("{} argument must be a reference", kind),
+        ))
+    }
+}
+
+/// Returns `T` if the given type is `ProcResult<T>`.
+fn proc_result_inner(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "ProcResult" {
+        return None;
+    }
+    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+        if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+            return Some(inner.clone());
+        }
+    }
+    None
+}
+
+/// Generates the hidden items `graph!` relies on: `__input_<port>` and `__output_<port>` constants
+/// holding each port's index, so ports are resolved by name and unknown ones are rejected at
+/// compile time, and a `__graph_node` method returning the nodes to connect inputs to and read
+/// outputs from, which are the same node for a processor.
+fn graph_hooks(
+    struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    inputs: &[syn::Ident],
+    outputs: &[syn::Ident],
+) -> proc_macro2::TokenStream {
+    let (ig, tg, wc) = generics.split_for_impl();
+    let port_indices = port_indices(inputs, outputs);
+    quote! {
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        impl #ig #struct_name #tg #wc {
+            #port_indices
+
+            pub fn __graph_node(self, graph: &raug::graph::Graph) -> (raug::graph::node::Node, raug::graph::node::Node) {
+                let node = graph.node(self);
+                (node, node)
+            }
+        }
+    }
+}
+
+/// Generates the `__INPUT_COUNT`, `__input_<port>` and `__output_<port>` constants of `graph_hooks`.
+fn port_indices(inputs: &[syn::Ident], outputs: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let input_consts = inputs
+        .iter()
+        .map(|port| format_ident!("__input_{}", port, span = port.span()));
+    let output_consts = outputs
+        .iter()
+        .map(|port| format_ident!("__output_{}", port, span = port.span()));
+    let input_count = inputs.len();
+    let input_indices = 0..inputs.len();
+    let output_indices = 0..outputs.len();
+    quote! {
+        pub const __INPUT_COUNT: usize = #input_count;
+        #(pub const #input_consts: usize = #input_indices;)*
+        #(pub const #output_consts: usize = #output_indices;)*
+    }
+}
+
 pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated.parse(attr);
 
+    let mut errors = None;
     let mut extra_derives = vec![];
+    let mut extra_attrs = vec![];
     let mut allocate_fn = None;
     let mut resize_buffers_fn = None;
-    if let Ok(args) = args {
-        for arg in args.iter() {
-            if let syn::Meta::List(meta_list) = arg {
-                if meta_list.path.is_ident("derive") {
-                    meta_list
-                        .parse_nested_meta(|meta| {
-                            let ident = meta.path.get_ident().unwrap();
-                            extra_derives.push(ident.clone());
-                            Ok(())
-                        })
-                        .unwrap();
-                } else {
-                    return syn::Error::new_spanned(
-                        meta_list.path.clone(),
-                        "Unknown attribute. Only `derive` is supported.",
-                    )
-                    .to_compile_error()
-                    .into();
-                }
-            } else if let syn::Meta::NameValue(meta_name_value) = arg {
-                if meta_name_value.path.is_ident("allocate") {
-                    if let syn::Expr::Path(path) = &meta_name_value.value {
-                        allocate_fn = Some(path.path.clone());
+    let mut register = None;
+    let mut output_names = None;
+    let mut oversample = None;
+    let mut poly: Option<PolyOptions> = None;
+    let mut voice_stealing = None;
+    let mut poly_outputs = None;
+    let mut channels = None;
+    let mut bypass = None;
+    let mut mix = None;
+    let mut dry = None;
+    let mut wet = None;
+    let mut flush_denormals_flag = None;
+    let mut guard_nan = None;
+    let mut realtime = false;
+    let mut fuzz = false;
+    match args {
+        Ok(args) => {
+            for arg in args.iter() {
+                if let syn::Meta::Path(path) = arg {
+                    if path.is_ident("register") {
+                        register = Some(path.clone());
+                    } else if path.is_ident("bypass") {
+                        bypass = Some(path.clone());
+                    } else if path.is_ident("mix") {
+                        mix = Some(path.clone());
+                    } else if path.is_ident("flush_denormals") {
+                        flush_denormals_flag = Some(path.span());
+                    } else if path.is_ident("realtime") {
+                        realtime = true;
+                    } else if path.is_ident("fuzz") {
+                        fuzz = true;
                     } else {
-                        return syn::Error::new_spanned(
-                            meta_name_value.value.clone(),
-                            "Expected a path for `allocate_fn`",
-                        )
-                        .to_compile_error()
-                        .into();
+                        push_error(&mut errors, unknown_processor_arg(path, ArgKind::Flag));
+                    }
+                } else if let syn::Meta::List(meta_list) = arg {
+                    if meta_list.path.is_ident("outputs") {
+                        match meta_list.parse_args_with(
+                            Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated,
+                        ) {
+                            Ok(names) => output_names = Some((meta_list.clone(), names)),
+                            Err(err) => push_error(&mut errors, err),
+                        }
+                    } else if !parse_struct_option(
+                        meta_list,
+                        &mut extra_derives,
+                        &mut extra_attrs,
+                        &mut errors,
+                    ) {
+                        push_error(
+                            &mut errors,
+                            unknown_processor_arg(&meta_list.path, ArgKind::List),
+                        );
                     }
-                } else if meta_name_value.path.is_ident("resize_buffers") {
-                    if let syn::Expr::Path(path) = &meta_name_value.value {
-                        resize_buffers_fn = Some(path.path.clone());
+                } else if let syn::Meta::NameValue(meta_name_value) = arg {
+                    if meta_name_value.path.is_ident("allocate") {
+                        if let syn::Expr::Path(path) = &meta_name_value.value {
+                            allocate_fn = Some(path.path.clone());
+                        } else {
+                            push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected a path for `allocate`",
+                                ),
+                            );
+                        }
+                    } else if meta_name_value.path.is_ident("resize_buffers") {
+                        if let syn::Expr::Path(path) = &meta_name_value.value {
+                            resize_buffers_fn = Some(path.path.clone());
+                        } else {
+                            push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected a path for `resize_buffers`",
+                                ),
+                            );
+                        }
+                    } else if meta_name_value.path.is_ident("oversample") {
+                        let factor = if let syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(lit),
+                            ..
+                        }) = &meta_name_value.value
+                        {
+                            lit.base10_parse::<usize>().ok()
+                        } else {
+                            None
+                        };
+                        match factor {
+                            Some(factor @ (2 | 4 | 8)) => oversample = Some(factor),
+                            _ => push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected 2, 4 or 8 for `oversample`",
+                                ),
+                            ),
+                        }
+                    } else if meta_name_value.path.is_ident("poly") {
+                        let voices = if let syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(lit),
+                            ..
+                        }) = &meta_name_value.value
+                        {
+                            lit.base10_parse::<usize>().ok()
+                        } else {
+                            None
+                        };
+                        match voices {
+                            Some(voices) if voices > 0 => {
+                                poly = Some(PolyOptions::new(voices, meta_name_value.path.span()))
+                            }
+                            _ => push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected a positive number of voices for `poly`",
+                                ),
+                            ),
+                        }
+                    } else if meta_name_value.path.is_ident("voice_stealing") {
+                        match parse_voice_stealing(&meta_name_value.value) {
+                            Ok(stealing) => {
+                                voice_stealing = Some((meta_name_value.path.clone(), stealing))
+                            }
+                            Err(err) => push_error(&mut errors, err),
+                        }
+                    } else if meta_name_value.path.is_ident("poly_outputs") {
+                        match parse_poly_outputs(&meta_name_value.value) {
+                            Ok(per_voice) => {
+                                poly_outputs = Some((meta_name_value.path.clone(), per_voice))
+                            }
+                            Err(err) => push_error(&mut errors, err),
+                        }
+                    } else if meta_name_value.path.is_ident("channels") {
+                        let count = if let syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(lit),
+                            ..
+                        }) = &meta_name_value.value
+                        {
+                            lit.base10_parse::<usize>().ok()
+                        } else {
+                            None
+                        };
+                        match count {
+                            Some(count) if count > 1 => {
+                                channels = Some(ChannelsOptions {
+                                    channels: count,
+                                    span: meta_name_value.path.span(),
+                                })
+                            }
+                            _ => push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected at least 2 channels for `channels`",
+                                ),
+                            ),
+                        }
+                    } else if meta_name_value.path.is_ident("guard_nan") {
+                        match parse_guard_nan(&meta_name_value.value) {
+                            Ok(guard) => guard_nan = Some(guard),
+                            Err(err) => push_error(&mut errors, err),
+                        }
+                    } else if meta_name_value.path.is_ident("dry")
+                        || meta_name_value.path.is_ident("wet")
+                    {
+                        let name = if let syn::Expr::Path(path) = &meta_name_value.value {
+                            path.path.get_ident().cloned()
+                        } else {
+                            None
+                        };
+                        match name {
+                            Some(name) if meta_name_value.path.is_ident("dry") => {
+                                dry = Some((meta_name_value.path.clone(), name))
+                            }
+                            Some(name) => wet = Some((meta_name_value.path.clone(), name)),
+                            None => push_error(
+                                &mut errors,
+                                syn::Error::new_spanned(
+                                    meta_name_value.value.clone(),
+                                    "Expected a port name",
+                                ),
+                            ),
+                        }
                     } else {
-                        return syn::Error::new_spanned(
-                            meta_name_value.value.clone(),
-                            "Expected a path for `resize_buffers_fn`",
-                        )
-                        .to_compile_error()
-                        .into();
+                        push_error(
+                            &mut errors,
+                            unknown_processor_arg(&meta_name_value.path, ArgKind::KeyValue),
+                        );
                     }
-                } else {
-                    return syn::Error::new_spanned(
-                        meta_name_value.path.clone(),
-                        "Unknown attribute. Only `allocate_fn` and `resize_buffers_fn` are supported.",
-                    )
-                    .to_compile_error()
-                    .into();
                 }
             }
         }
+        Err(err) => push_error(&mut errors, err),
+    }
+
+    if let Some(poly) = &mut poly {
+        if let Some((_, stealing)) = voice_stealing {
+            poly.stealing = stealing;
+        }
+        if let Some((_, per_voice)) = poly_outputs {
+            poly.per_voice_outputs = per_voice;
+        }
+    } else {
+        let paths = [
+            voice_stealing.map(|(path, _)| path),
+            poly_outputs.map(|(path, _)| path),
+        ];
+        for path in paths.into_iter().flatten() {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(path.clone(), "This option requires `poly`"),
+            );
+        }
+    }
+
+    if let (Some(channels), Some(_)) = (&channels, oversample) {
+        push_error(
+            &mut errors,
+            syn::Error::new(
+                channels.span,
+                "`channels` cannot be combined with `oversample`",
+            ),
+        );
+    }
+    if channels.is_some() {
+        // `<Name>Multi` has no per-channel instance of the struct to pass to the hooks.
+        for (option, path) in [
+            ("allocate", &allocate_fn),
+            ("resize_buffers", &resize_buffers_fn),
+        ] {
+            if let Some(path) = path {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        path.clone(),
+                        format!("`{}` cannot be combined with `channels`", option),
+                    ),
+                );
+            }
+        }
     }
 
     let item = parse_macro_input!(item as syn::ItemFn);
     let vis = item.vis.clone();
     let (ig, tg, wc) = item.sig.generics.split_for_impl();
+
+    if let Some(register) = &register {
+        if !item.sig.generics.params.is_empty() {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    register.clone(),
+                    "Generic processors cannot be registered",
+                ),
+            );
+        }
+    }
+
     let func_name = item.sig.ident.clone().to_string();
     let struct_name = func_name.to_case(Case::Pascal);
     let struct_name = format_ident!("{}", struct_name);
     let attrs = item.attrs.clone();
 
     let mut proc_env_ident = None;
+    let mut context = vec![];
+    let mut shared_state = vec![];
 
     let mut phantom_data = vec![];
     let mut state = vec![];
@@ -97,9 +644,15 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
     let mut update_args = vec![];
     let mut update_call_args = vec![];
     let mut get_inputs = vec![];
-    let mut get_outputs = vec![];
+    let mut output_ports = vec![];
     let mut assign_inputs = vec![];
     let mut assign_outputs = vec![];
+    let mut assert_copy_inputs = vec![];
+    let mut upsample_inputs = vec![];
+    let mut oversample_outputs = vec![];
+    let mut shadow_outputs = vec![];
+    let mut downsample_outputs = vec![];
+    let mut guard_outputs = vec![];
 
     for generic in item.sig.generics.params.iter() {
         if let syn::GenericParam::Type(ty) = generic {
@@ -113,161 +666,383 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
     }
 
     for arg in item.sig.inputs.iter() {
-        if let syn::FnArg::Typed(arg) = arg {
-            if let syn::Type::Path(type_path) = &*arg.ty {
-                if type_path.path.is_ident("ProcEnv") {
-                    if proc_env_ident.is_some() {
-                        return syn::Error::new_spanned(
+        let arg = match arg {
+            syn::FnArg::Typed(arg) => arg,
+            syn::FnArg::Receiver(receiver) => {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        receiver.clone(),
+                        "Processor functions cannot take `self`",
+                    ),
+                );
+                continue;
+            }
+        };
+        if let syn::Type::Path(type_path) = &*arg.ty {
+            if is_proc_env(&type_path.path) {
+                if proc_env_ident.is_some() {
+                    push_error(
+                        &mut errors,
+                        syn::Error::new_spanned(
                             arg.pat.clone(),
                             "Only one ProcEnv argument is allowed",
-                        )
-                        .to_compile_error()
-                        .into();
+                        ),
+                    );
+                }
+                proc_env_ident = Some(arg.pat.clone());
+                continue;
+            }
+        }
+        let Some(attr) = arg.attrs.first() else {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    arg.pat.clone(),
+                    "Expected a `#[state]`, `#[input]` or `#[output]` attribute on this argument",
+                ),
+            );
+            continue;
+        };
+        if attr.path().is_ident("state") {
+            let mut shared = None;
+            let name = arg_name(arg, attr, "State", Some(&mut shared));
+            if let (Ok(name), Some(shared)) = (&name, shared) {
+                shared_state.push((name.clone(), shared));
+            }
+            let ty = reference_elem(arg, "State", true);
+            match (name, ty) {
+                (Ok(name), Ok(ty)) => state.push(ProcessorArg {
+                    name,
+                    pat: *arg.pat.clone(),
+                    ty,
+                    by_value: false,
+                }),
+                (name, ty) => {
+                    for err in [name.err(), ty.err()].into_iter().flatten() {
+                        push_error(&mut errors, err);
                     }
-                    proc_env_ident = Some(arg.pat.clone());
-                    continue;
                 }
             }
-            if let Some(attr) = arg.attrs.first() {
-                if attr.path().is_ident("state") {
-                    let name = if let syn::Pat::Ident(pat_ident) = &*arg.pat {
-                        pat_ident.ident.clone()
-                    } else {
-                        return syn::Error::new_spanned(
-                            arg.pat.clone(),
-                            "State argument must be a named identifier",
-                        )
-                        .to_compile_error()
-                        .into();
-                    };
-                    let ty = *arg.ty.clone();
-                    state.push(ProcessorArg { name, ty });
-                } else if attr.path().is_ident("input") {
-                    let name = if let syn::Pat::Ident(pat_ident) = &*arg.pat {
-                        pat_ident.ident.clone()
-                    } else {
-                        return syn::Error::new_spanned(
-                            arg.pat.clone(),
-                            "Input argument must be a named identifier",
-                        )
-                        .to_compile_error()
-                        .into();
-                    };
-                    let ty;
-                    if let syn::Type::Group(group) = &*arg.ty {
-                        if let syn::Type::Reference(reference) = &*group.elem {
-                            if reference.mutability.is_some() {
-                                return syn::Error::new_spanned(
-                                    arg.pat.clone(),
-                                    "Input argument must be immutable reference",
-                                )
-                                .to_compile_error()
-                                .into();
-                            }
-                            ty = *reference.elem.clone();
-                        } else {
-                            return syn::Error::new_spanned(
-                                arg.pat.clone(),
-                                "Input argument must be a reference inside a group",
-                            )
-                            .to_compile_error()
-                            .into();
-                        }
-                    } else if let syn::Type::Reference(reference) = &*arg.ty {
-                        if reference.mutability.is_some() {
-                            return syn::Error::new_spanned(
-                                arg.pat.clone(),
-                                "Input argument must be immutable reference",
-                            )
-                            .to_compile_error()
-                            .into();
-                        }
-                        ty = *reference.elem.clone();
-                    } else {
-                        return syn::Error::new_spanned(
-                            arg.pat.clone(),
-                            "Input argument must be a reference",
-                        )
-                        .to_compile_error()
-                        .into();
+        } else if attr.path().is_ident("input") {
+            let name = arg_name(arg, attr, "Input", None);
+            let by_value = !is_reference(&arg.ty);
+            let ty = if by_value {
+                Ok(ungroup(&arg.ty).clone())
+            } else {
+                reference_elem(arg, "Input", false)
+            };
+            match (name, ty) {
+                (Ok(name), Ok(ty)) => input.push(ProcessorArg {
+                    name,
+                    pat: *arg.pat.clone(),
+                    ty,
+                    by_value,
+                }),
+                (name, ty) => {
+                    for err in [name.err(), ty.err()].into_iter().flatten() {
+                        push_error(&mut errors, err);
                     }
-                    input.push(ProcessorArg { name, ty });
-                } else if attr.path().is_ident("output") {
-                    let name = if let syn::Pat::Ident(pat_ident) = &*arg.pat {
-                        pat_ident.ident.clone()
-                    } else {
-                        return syn::Error::new_spanned(
-                            arg.pat.clone(),
-                            "Output argument must be a named identifier",
-                        )
-                        .to_compile_error()
-                        .into();
-                    };
-                    let ty;
-                    if let syn::Type::Group(group) = &*arg.ty {
-                        if let syn::Type::Reference(reference) = &*group.elem {
-                            if reference.mutability.is_none() {
-                                return syn::Error::new_spanned(
-                                    arg.pat.clone(),
-                                    "Output argument must be mutable reference",
-                                )
-                                .to_compile_error()
-                                .into();
-                            }
-                            ty = *reference.elem.clone();
-                        } else {
-                            return syn::Error::new_spanned(
-                                arg.pat.clone(),
-                                "Output argument must be a reference inside a group",
-                            )
-                            .to_compile_error()
-                            .into();
-                        }
-                    } else if let syn::Type::Reference(reference) = &*arg.ty {
-                        if reference.mutability.is_none() {
-                            return syn::Error::new_spanned(
-                                arg.pat.clone(),
-                                "Output argument must be mutable reference",
-                            )
-                            .to_compile_error()
-                            .into();
-                        }
-                        ty = *reference.elem.clone();
-                    } else {
-                        return syn::Error::new_spanned(
-                            arg.pat.clone(),
-                            "Output argument must be a reference",
-                        )
-                        .to_compile_error()
-                        .into();
+                }
+            }
+        } else if attr.path().is_ident("output") {
+            let name = arg_name(arg, attr, "Output", None);
+            let ty = reference_elem(arg, "Output", true);
+            match (name, ty) {
+                (Ok(name), Ok(ty)) => output.push(ProcessorArg {
+                    name,
+                    pat: *arg.pat.clone(),
+                    ty,
+                    by_value: false,
+                }),
+                (name, ty) => {
+                    for err in [name.err(), ty.err()].into_iter().flatten() {
+                        push_error(&mut errors, err);
                     }
-                    output.push(ProcessorArg { name, ty });
-                } else {
-                    return syn::Error::new_spanned(
-                        attr.path().clone(),
-                        "Unknown attribute. Only `state`, `input`, and `output` are supported.",
-                    )
-                    .to_compile_error()
-                    .into();
                 }
+            }
+        } else if let Some(kind) = attr
+            .path()
+            .get_ident()
+            .filter(|ident| ["sample_index", "time", "block_size"].contains(&&*ident.to_string()))
+        {
+            if context.iter().any(|(other, _, _)| other == kind) {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        arg.pat.clone(),
+                        format!("Only one `#[{}]` argument is allowed", kind),
+                    ),
+                );
+            }
+            context.push((kind.clone(), *arg.pat.clone(), *arg.ty.clone()));
+        } else {
+            let mut message = "Unknown attribute. Only `state`, `input`, `output`, `sample_index`, `time`, and `block_size` are supported.".to_string();
+            if let Some(suggestion) = attr
+                .path()
+                .get_ident()
+                .and_then(|ident| suggest(&ident.to_string(), ARG_ATTRS))
+            {
+                message.push_str(&format!(" Did you mean `#[{}]`?", suggestion));
+            }
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(attr.path().clone(), message),
+            );
+        }
+    }
+
+    // The returned value is either the function's return type, or the `T` in `ProcResult<T>`.
+    let (return_ty, fallible) = match &item.sig.output {
+        syn::ReturnType::Default => (syn::parse_quote!(()), false),
+        syn::ReturnType::Type(_, ty) => match proc_result_inner(ty) {
+            Some(inner) => (inner, true),
+            None => (*ty.clone(), false),
+        },
+    };
+    let return_tys = match &return_ty {
+        syn::Type::Tuple(tuple) => tuple.elems.iter().cloned().collect::<Vec<_>>(),
+        ty => vec![ty.clone()],
+    };
+    let mut returned = vec![];
+    let output_names_given = output_names.is_some();
+    match output_names {
+        Some((meta_list, names)) => {
+            if names.len() != return_tys.len() {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        meta_list,
+                        format!(
+                            "Expected {} output names for the return type, got {}",
+                            return_tys.len(),
+                            names.len()
+                        ),
+                    ),
+                );
             } else {
-                return syn::Error::new_spanned(
-                    arg.pat.clone(),
-                    "Expected a function argument with attributes",
+                for (name, ty) in names.into_iter().zip(return_tys) {
+                    returned.push(ProcessorArg {
+                        pat: syn::parse_quote!(#name),
+                        name,
+                        ty,
+                        by_value: true,
+                    });
+                }
+            }
+        }
+        None if return_tys.len() == 1 => returned.push(ProcessorArg {
+            name: format_ident!("out"),
+            pat: syn::parse_quote!(out),
+            ty: return_tys[0].clone(),
+            by_value: true,
+        }),
+        None => {
+            for (i, ty) in return_tys.into_iter().enumerate() {
+                let name = format_ident!("out{}", i);
+                returned.push(ProcessorArg {
+                    pat: syn::parse_quote!(#name),
+                    name,
+                    ty,
+                    by_value: true,
+                });
+            }
+        }
+    }
+
+    // Returned outputs share the namespace of `#[output]` arguments, including the default names.
+    for arg in returned.iter() {
+        if output.iter().any(|other| other.name == arg.name) {
+            let message = format!(
+                "The returned output `{}` has the same name as an `#[output]` argument",
+                arg.name
+            );
+            let error = if output_names_given {
+                syn::Error::new_spanned(arg.name.clone(), message)
+            } else {
+                syn::Error::new_spanned(
+                    item.sig.output.clone(),
+                    format!("{}; name the returned outputs with `outputs(...)`", message),
                 )
-                .to_compile_error()
-                .into();
+            };
+            push_error(&mut errors, error);
+        }
+    }
+
+    if channels.is_none() {
+        for (_, path) in shared_state.iter() {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(path.clone(), "`shared` state requires `channels`"),
+            );
+        }
+    }
+
+    if oversample.is_some() {
+        let ports = input.iter().chain(output.iter()).chain(returned.iter());
+        if let Some(err) = check_oversample_ports(ports.map(|arg| &arg.ty)) {
+            push_error(&mut errors, err);
+        }
+    }
+
+    let mut realtime_warnings = quote! {};
+    if realtime {
+        let args = state.iter().chain(input.iter()).chain(output.iter());
+        let (err, warnings) = check_realtime(&item.block, &attrs, args);
+        if let Some(err) = err {
+            push_error(&mut errors, err);
+        }
+        realtime_warnings = warnings;
+    }
+
+    // Bypass and mix route the designated `dry` input to the designated `wet` output.
+    let mut dry_wet = None;
+    if let Some(flag) = bypass.as_ref().or(mix.as_ref()) {
+        for (name, arg) in [("bypass", &bypass), ("mix", &mix)] {
+            if arg.is_some() && input.iter().any(|arg| arg.name == name) {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        flag.clone(),
+                        format!("An input named `{}` already exists", name),
+                    ),
+                );
+            }
+        }
+        if poly.is_some() || channels.is_some() {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    flag.clone(),
+                    "`bypass` and `mix` cannot be combined with `poly` or `channels`",
+                ),
+            );
+        }
+        if let (Some(mix), Some(_)) = (&mix, oversample) {
+            // The oversampled output lags the dry input by the resampling filters' delay.
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    mix.clone(),
+                    "`mix` cannot be combined with `oversample`, since the wet output is delayed by the resampling filters",
+                ),
+            );
+        }
+        let dry_arg = match &dry {
+            Some((path, name)) => input.iter().find(|arg| arg.name == *name).ok_or_else(|| {
+                syn::Error::new_spanned(path.clone(), format!("No input named `{}`", name))
+            }),
+            None => input.first().ok_or_else(|| {
+                syn::Error::new_spanned(flag.clone(), "This option requires at least one input")
+            }),
+        };
+        let wet_arg = match &wet {
+            Some((path, name)) => output
+                .iter()
+                .chain(returned.iter())
+                .find(|arg| arg.name == *name)
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(path.clone(), format!("No output named `{}`", name))
+                }),
+            None => output.iter().chain(returned.iter()).next().ok_or_else(|| {
+                syn::Error::new_spanned(flag.clone(), "This option requires at least one output")
+            }),
+        };
+        match (dry_arg, wet_arg) {
+            (Ok(dry_arg), Ok(wet_arg)) => {
+                let (dry_ty, wet_ty) = (&dry_arg.ty, &wet_arg.ty);
+                if quote!(#dry_ty).to_string() != quote!(#wet_ty).to_string() {
+                    push_error(
+                        &mut errors,
+                        syn::Error::new_spanned(
+                            flag.clone(),
+                            format!(
+                                "The dry input `{}` and wet output `{}` must have the same type",
+                                dry_arg.name, wet_arg.name
+                            ),
+                        ),
+                    );
+                } else if let (Some(mix), false) = (&mix, is_f32(dry_ty)) {
+                    push_error(
+                        &mut errors,
+                        syn::Error::new_spanned(
+                            mix.clone(),
+                            "`mix` requires the dry input and wet output to be `f32`",
+                        ),
+                    );
+                }
+                dry_wet = Some((dry_arg.name.clone(), wet_arg.name.clone()));
+            }
+            (dry_arg, wet_arg) => {
+                for err in [dry_arg.err(), wet_arg.err()].into_iter().flatten() {
+                    push_error(&mut errors, err);
+                }
             }
-        } else {
-            return syn::Error::new_spanned(
-                arg.clone(),
-                "Expected a function argument with attributes",
-            )
-            .to_compile_error()
-            .into();
         }
+    } else {
+        for path in [dry.map(|(path, _)| path), wet.map(|(path, _)| path)]
+            .into_iter()
+            .flatten()
+        {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(path, "This option requires `bypass` or `mix`"),
+            );
+        }
+    }
+
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
     }
 
+    let channels_impl = if let Some(channels) = &channels {
+        let shared_state = shared_state
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        let args = ChannelsArgs {
+            state: &state,
+            shared_state: &shared_state,
+            input: &input,
+            output: &output,
+            returned: &returned,
+            returns_tuple: matches!(return_ty, syn::Type::Tuple(_)),
+            context: &context,
+            flush_denormals: flush_denormals_flag,
+            guard_nan,
+        };
+        match channels_processor(
+            channels,
+            &struct_name,
+            &vis,
+            &item.sig.generics,
+            &extra_derives,
+            args,
+        ) {
+            Ok(channels_impl) => channels_impl,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote! {}
+    };
+
+    let poly_impl = if let Some(poly) = &poly {
+        let outputs = output.iter().chain(returned.iter()).collect::<Vec<_>>();
+        match poly_processor(
+            poly,
+            &struct_name,
+            &vis,
+            &item.sig.generics,
+            &input,
+            &outputs,
+        ) {
+            Ok(poly_impl) => poly_impl,
+            Err(err) => return err.to_compile_error().into(),
+        }
+    } else {
+        quote! {}
+    };
+
     let proc_env_decl = if let Some(proc_env_ident) = proc_env_ident {
         quote! {
             let #proc_env_ident = env;
@@ -277,31 +1052,15 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
     };
 
     let mut struct_fields = vec![];
+    let mut advance_time = quote! {};
+    let mut reset_time = quote! {};
     for arg in state.iter() {
-        let ProcessorArg { name, ty, .. } = arg;
-        let ty = if let syn::Type::Reference(ty) = ty {
-            if ty.mutability.is_none() {
-                return syn::Error::new_spanned(
-                    ty.clone(),
-                    "State argument must be mutable reference",
-                )
-                .to_compile_error()
-                .into();
-            }
-            ty.elem.clone()
-        } else {
-            return syn::Error::new_spanned(
-                ty.clone(),
-                "State argument must be a mutable reference",
-            )
-            .to_compile_error()
-            .into();
-        };
+        let ProcessorArg { name, pat, ty, .. } = arg;
         struct_fields.push(quote! {
             pub #name: #ty,
         });
         update_args.push(quote! {
-            #name: &mut #ty,
+            #pat: &mut #ty,
         });
         update_call_args.push(quote! {
             &mut self.#name,
@@ -309,7 +1068,12 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
     }
 
     for (arg_index, arg) in input.iter().enumerate() {
-        let ProcessorArg { name, ty } = arg;
+        let ProcessorArg {
+            name,
+            pat,
+            ty,
+            by_value,
+        } = arg;
 
         struct_fields.push(quote! {
             pub #name: #ty,
@@ -317,24 +1081,90 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         input_spec.push(quote! {
             raug::processor::io::SignalSpec::new(stringify!(#name), <#ty as raug::signal::Signal>::signal_type())
         });
-        get_inputs.push(quote! {
-            let #name = &inputs.input_as::<#ty>(#arg_index);
-        });
+        get_inputs.push(get_input(&struct_name, name, ty, arg_index));
         assign_inputs.push(quote! {
             if let Some(#name) = #name.map(|inp| &inp[__i]) {
                 Clone::clone_from(&mut self.#name, #name);
             }
         });
-        update_args.push(quote! {
-            #name: &#ty,
+        // Oversampled inputs are read from the upsampled buffer instead of the struct field.
+        let value = if let Some(factor) = oversample {
+            let up_state = format_ident!("__os_up_{}", name);
+            let up_samples = format_ident!("__os_in_{}", name);
+            let stages = factor.trailing_zeros() as usize;
+            // The filter state is public, like `__elapsed_samples`, so the struct can still be
+            // built with a literal outside its module.
+            struct_fields.push(quote! {
+                #[doc(hidden)]
+                pub #up_state: [[f32; 16]; #stages],
+            });
+            upsample_inputs.push(quote! {
+                let #up_samples = {
+                    let mut samples = [0.0f32; #factor];
+                    samples[0] = self.#name;
+                    let mut len = 1;
+                    for stage in self.#up_state.iter_mut() {
+                        let prev = samples;
+                        for j in 0..len {
+                            let [a, b] = Self::__halfband_upsample(stage, prev[j]);
+                            samples[2 * j] = a;
+                            samples[2 * j + 1] = b;
+                        }
+                        len *= 2;
+                    }
+                    samples
+                };
+            });
+            quote! { #up_samples[__k] }
+        } else {
+            quote! { self.#name }
+        };
+        if *by_value {
+            // By-value inputs are copied out of the struct, so make sure that's possible.
+            assert_copy_inputs.push(quote_spanned! {ty.span()=>
+                assert_copy::<#ty>();
+            });
+            update_args.push(quote! {
+                #pat: #ty,
+            });
+            update_call_args.push(quote! {
+                #value,
+            });
+        } else {
+            update_args.push(quote! {
+                #pat: &#ty,
+            });
+            update_call_args.push(quote! {
+                &#value,
+            });
+        }
+    }
+
+    let mut control_inputs: Vec<(syn::Ident, syn::Type)> = vec![];
+    if bypass.is_some() {
+        control_inputs.push((format_ident!("bypass"), syn::parse_quote!(bool)));
+    }
+    if mix.is_some() {
+        control_inputs.push((format_ident!("mix"), syn::parse_quote!(f32)));
+    }
+    for (name, ty) in control_inputs.iter() {
+        let arg_index = input_spec.len();
+        struct_fields.push(quote! {
+            pub #name: #ty,
         });
-        update_call_args.push(quote! {
-            &self.#name,
+        input_spec.push(quote! {
+            raug::processor::io::SignalSpec::new(stringify!(#name), <#ty as raug::signal::Signal>::signal_type())
+        });
+        get_inputs.push(get_input(&struct_name, name, ty, arg_index));
+        assign_inputs.push(quote! {
+            if let Some(#name) = #name.map(|inp| &inp[__i]) {
+                Clone::clone_from(&mut self.#name, #name);
+            }
         });
     }
 
-    for (arg_index, arg) in output.iter().enumerate() {
-        let ProcessorArg { name, ty } = arg;
+    for (arg_index, arg) in output.iter().chain(returned.iter()).enumerate() {
+        let ProcessorArg { name, pat, ty, .. } = arg;
 
         output_spec.push(quote! {
             raug::processor::io::SignalSpec::new(stringify!(#name), <#ty as raug::signal::Signal>::signal_type())
@@ -342,24 +1172,116 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         create_output_buffers.push(quote! {
             raug::signal::type_erased::AnyBuffer::zeros::<#ty>(size)
         });
-        update_args.push(quote! {
-            #name: &mut #ty,
-        });
-        update_call_args.push(quote! {
-            #name,
-        });
-        get_outputs.push(quote! {
-            // SAFETY: We won't ever get the same output buffer twice, so there's no way to alias it.
-            let mut #name = unsafe { outputs.output_extended_lifetime(#arg_index) };
-        });
+        if arg_index < output.len() {
+            update_args.push(quote! {
+                #pat: &mut #ty,
+            });
+            update_call_args.push(quote! {
+                #name,
+            });
+        }
+        output_ports.push(name.clone());
+        let slot = output_slot(
+            &struct_name,
+            quote! { stringify!(#name) },
+            ty,
+            quote! { #name },
+        );
         assign_outputs.push(quote! {
-            let #name = #name.get_mut_as::<#ty>(__i).unwrap();
+            let #name = #slot;
         });
+        if let (Some(guard), true) = (guard_nan, is_f32(ty)) {
+            guard_outputs.push(guard_output(guard, &struct_name, name, name));
+        }
+        if let Some(factor) = oversample {
+            // Oversampled outputs are written to a buffer and decimated back into the output slot.
+            let down_state = format_ident!("__os_down_{}", name);
+            let down_samples = format_ident!("__os_out_{}", name);
+            let stages = factor.trailing_zeros() as usize;
+            struct_fields.push(quote! {
+                #[doc(hidden)]
+                pub #down_state: [[f32; 24]; #stages],
+            });
+            oversample_outputs.push(quote! {
+                let mut #down_samples = [0.0f32; #factor];
+            });
+            shadow_outputs.push(quote! {
+                let #name = &mut #down_samples[__k];
+            });
+            downsample_outputs.push(quote! {
+                *#name = {
+                    let mut samples = #down_samples;
+                    let mut len = #factor;
+                    for stage in self.#down_state.iter_mut() {
+                        len /= 2;
+                        for j in 0..len {
+                            samples[j] = Self::__halfband_downsample(stage, samples[2 * j], samples[2 * j + 1]);
+                        }
+                    }
+                    samples[0]
+                };
+            });
+        }
     }
 
+    for (kind, pat, ty) in context.iter() {
+        update_args.push(quote! {
+            #pat: #ty,
+        });
+        if kind == "sample_index" {
+            update_call_args.push(quote! {
+                __i as #ty,
+            });
+        } else if kind == "block_size" {
+            update_call_args.push(quote! {
+                inputs.block_size() as #ty,
+            });
+        } else {
+            // Time is tracked in a hidden field counting the samples processed since `allocate`.
+            // It's public so the struct can still be built with a literal outside its module.
+            struct_fields.push(quote! {
+                #[doc(hidden)]
+                pub __elapsed_samples: u64,
+            });
+            reset_time = quote! {
+                self.__elapsed_samples = 0;
+            };
+            let subsample = if let Some(factor) = oversample {
+                quote! { + __k as f64 / #factor as f64 }
+            } else {
+                quote! {}
+            };
+            update_call_args.push(quote! {
+                ((self.__elapsed_samples + __i as u64) as f64 #subsample / inputs.env.sample_rate as f64) as #ty,
+            });
+            advance_time = quote! {
+                self.__elapsed_samples += inputs.block_size() as u64;
+            };
+        }
+    }
+
+    // An unconnected `mix` input is fully wet, so `Default` is implemented by hand to start there.
+    let derive_default = mix.is_some()
+        && extra_derives.iter().any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Default")
+        });
+    let struct_derives = extra_derives
+        .iter()
+        .filter(|path| {
+            !derive_default
+                || path
+                    .segments
+                    .last()
+                    .is_some_and(|seg| seg.ident != "Default")
+        })
+        .collect::<Vec<_>>();
+
     let struct_def = quote! {
         #(#attrs)*
-        #[derive(#(#extra_derives),*)]
+        #[derive(#(#struct_derives),*)]
+        #(#[#extra_attrs])*
         #[allow(missing_docs)]
         #vis struct #struct_name #tg #wc {
             #(#struct_fields)*
@@ -367,7 +1289,81 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         }
     };
 
+    let default_impl = if derive_default {
+        let fields = match syn::parse2::<syn::ItemStruct>(struct_def.clone()) {
+            Ok(item) => item.fields,
+            Err(err) => return err.to_compile_error().into(),
+        };
+        let field_defaults = fields
+            .iter()
+            .filter_map(|field| field.ident.as_ref())
+            .map(|name| {
+                if name == "mix" {
+                    quote! { #name: 1.0, }
+                } else {
+                    quote! { #name: Default::default(), }
+                }
+            });
+        // Like `derive(Default)`, require every type parameter to implement `Default`.
+        let mut generics = item.sig.generics.clone();
+        let type_params = generics
+            .type_params()
+            .map(|param| param.ident.clone())
+            .collect::<Vec<_>>();
+        let where_clause = generics.make_where_clause();
+        for param in type_params {
+            where_clause
+                .predicates
+                .push(syn::parse_quote!(#param: Default));
+        }
+        let (ig, tg, wc) = generics.split_for_impl();
+        quote! {
+            impl #ig Default for #struct_name #tg #wc {
+                fn default() -> Self {
+                    Self {
+                        #(#field_defaults)*
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let body = item.block.clone();
+    let body = if fallible {
+        quote! { #body }
+    } else {
+        quote! { Ok((move || -> #return_ty #body)()) }
+    };
+
+    let env = if oversample.is_some() {
+        quote! { __os_env }
+    } else {
+        quote! { inputs.env }
+    };
+
+    let returned_names = returned.iter().map(|arg| &arg.name).collect::<Vec<_>>();
+    let mut call_process_sample = match returned_names.as_slice() {
+        [] => quote! {
+            Self::process_sample(#env, #(#update_call_args)*)?;
+        },
+        [name] if !matches!(return_ty, syn::Type::Tuple(_)) => quote! {
+            *#name = Self::process_sample(#env, #(#update_call_args)*)?;
+        },
+        names => {
+            let values = names
+                .iter()
+                .map(|name| format_ident!("__{}", name))
+                .collect::<Vec<_>>();
+            quote! {
+                let (#(#values,)*) = Self::process_sample(#env, #(#update_call_args)*)?;
+                #(*#names = #values;)*
+            }
+        }
+    };
+
+    call_process_sample.extend(guard_outputs);
 
     let struct_update_impl = quote! {
         impl #ig #struct_name #tg #wc {
@@ -376,7 +1372,7 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
             #[allow(clippy::too_many_arguments)]
             #[allow(clippy::ptr_arg)]
             #[track_caller]
-            pub fn process_sample(env: raug::processor::io::ProcEnv, #(#update_args)*) -> raug::processor::ProcResult<()> {
+            pub fn process_sample(env: raug::processor::io::ProcEnv, #(#update_args)*) -> raug::processor::ProcResult<#return_ty> {
                 #proc_env_decl
                 #body
             }
@@ -386,8 +1382,11 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
     let mut node_inputs = vec![];
     let mut node_fn_args = vec![];
 
-    for input in input.iter() {
-        let ProcessorArg { name, .. } = input;
+    let input_names = input
+        .iter()
+        .map(|arg| &arg.name)
+        .chain(control_inputs.iter().map(|(name, _)| name));
+    for name in input_names {
         node_inputs.push(quote! {
             #name
         });
@@ -396,6 +1395,21 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         });
     }
 
+    let graph_hooks = graph_hooks(
+        &struct_name,
+        &item.sig.generics,
+        &input
+            .iter()
+            .map(|arg| arg.name.clone())
+            .chain(control_inputs.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>(),
+        &output
+            .iter()
+            .chain(returned.iter())
+            .map(|arg| arg.name.clone())
+            .collect::<Vec<_>>(),
+    );
+
     let node_fn_def = quote! {
         impl #ig #struct_name #tg #wc {
             #[doc = concat!("Adds a new ", stringify!(#struct_name), "node to the graph and connects its inputs.")]
@@ -417,16 +1431,19 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         }
     };
 
-    // let outputs = item.sig.output.clone();
-
-    let allocate_fn = if let Some(allocate_fn) = allocate_fn {
-        quote! {
+    let allocate_fn = match allocate_fn {
+        Some(allocate_fn) => quote! {
             fn allocate(&mut self, sample_rate: f32, block_size: usize) {
+                #reset_time
                 #allocate_fn(self, sample_rate, block_size);
             }
-        }
-    } else {
-        quote! {}
+        },
+        None if !reset_time.is_empty() => quote! {
+            fn allocate(&mut self, _sample_rate: f32, _block_size: usize) {
+                #reset_time
+            }
+        },
+        None => quote! {},
     };
 
     let resize_buffers_fn = if let Some(resize_buffers_fn) = resize_buffers_fn {
@@ -439,6 +1456,73 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
         quote! {}
     };
 
+    let assert_copy_inputs = if assert_copy_inputs.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn assert_copy<T: Copy>() {}
+            #(#assert_copy_inputs)*
+        }
+    };
+
+    let (oversample_setup, process_samples) = if let Some(factor) = oversample {
+        (
+            quote! {
+                let __os_env = raug::processor::io::ProcEnv {
+                    sample_rate: inputs.env.sample_rate * #factor as f32,
+                    ..inputs.env
+                };
+            },
+            quote! {
+                #(#upsample_inputs)*
+                #(#oversample_outputs)*
+                for __k in 0..#factor {
+                    #(#shadow_outputs)*
+                    #call_process_sample
+                }
+                #(#downsample_outputs)*
+            },
+        )
+    } else {
+        (quote! {}, call_process_sample)
+    };
+
+    let process_samples = if let Some((dry, wet)) = &dry_wet {
+        let mix_wet = if mix.is_some() {
+            quote! {
+                *#wet = self.#dry * (1.0 - self.mix) + *#wet * self.mix;
+            }
+        } else {
+            quote! {}
+        };
+        if bypass.is_some() {
+            quote! {
+                if self.bypass {
+                    Clone::clone_from(#wet, &self.#dry);
+                } else {
+                    #process_samples
+                    #mix_wet
+                }
+            }
+        } else {
+            quote! {
+                #process_samples
+                #mix_wet
+            }
+        }
+    } else {
+        process_samples
+    };
+
+    let flush_denormals = if let Some(span) = flush_denormals_flag {
+        flush_denormals(span)
+    } else {
+        quote! {}
+    };
+
+    let check_input_count = check_input_count(&struct_name, input_spec.len());
+    let split_outputs = split_outputs(&struct_name, &output_ports);
+
     let processor_impl = quote! {
         impl #ig raug::processor::Processor for #struct_name #tg #wc {
             fn name(&self) -> &str {
@@ -461,26 +1545,76 @@ pub fn processor_attribute(attr: TokenStream, item: TokenStream) -> TokenStream
             #resize_buffers_fn
 
             #[track_caller]
-            fn process(&mut self, inputs: raug::processor::io::ProcessorInputs, mut outputs: raug::processor::io::ProcessorOutputs) -> Result<(), raug::processor::ProcessorError> {
+            fn process(&mut self, inputs: raug::processor::io::ProcessorInputs, outputs: raug::processor::io::ProcessorOutputs) -> Result<(), raug::processor::ProcessorError> {
+                #assert_copy_inputs
+                #check_input_count
+
                 #(#get_inputs)*
-                #(#get_outputs)*
+                #split_outputs
+
+                #oversample_setup
+                #flush_denormals
 
                 for __i in 0..inputs.block_size() {
                     #(#assign_inputs)*
                     #(#assign_outputs)*
-                    Self::process_sample(inputs.env, #(#update_call_args)*)?;
+                    #process_samples
                 }
 
+                #advance_time
+
                 Ok(())
             }
         }
     };
 
+    let halfband_impl = if oversample.is_some() {
+        halfband_impl(&struct_name, &item.sig.generics)
+    } else {
+        quote! {}
+    };
+
+    let fuzz_impl = if fuzz {
+        let input_tys = input
+            .iter()
+            .map(|arg| &arg.ty)
+            .chain(control_inputs.iter().map(|(_, ty)| ty))
+            .collect::<Vec<_>>();
+        let outputs = output.iter().chain(returned.iter()).collect::<Vec<_>>();
+        fuzz_impl(&struct_name, &vis, &item.sig.generics, &input_tys, &outputs)
+    } else {
+        quote! {}
+    };
+
+    let register_impl = if register.is_some() {
+        quote! {
+            crate::__processor_registry_inventory::submit! {
+                crate::ProcessorRegistration {
+                    name: stringify!(#struct_name),
+                    module_path: module_path!(),
+                    input_spec: || raug::processor::Processor::input_spec(&<#struct_name as Default>::default()),
+                    output_spec: || raug::processor::Processor::output_spec(&<#struct_name as Default>::default()),
+                    create: || Box::new(<#struct_name as Default>::default()),
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     quote! {
         #struct_def
+        #default_impl
         #struct_update_impl
         #node_fn_def
+        #graph_hooks
         #processor_impl
+        #halfband_impl
+        #realtime_warnings
+        #register_impl
+        #fuzz_impl
+        #poly_impl
+        #channels_impl
     }
     .into()
 }
@@ -0,0 +1,116 @@
+use quote::quote;
+
+/// Returns early with an error describing a buffer of the wrong type or length.
+///
+/// `port` must evaluate to something displayable naming the port, and `buffer` to an `AnyBuffer`.
+fn buffer_mismatch(
+    processor: &syn::Ident,
+    direction: &str,
+    port: &proc_macro2::TokenStream,
+    ty: &syn::Type,
+    buffer: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let message = format!(
+        "{{}}: {} `{{}}` expected a {{:?}} buffer of at least {{}} samples, got a {{:?}} buffer of {{}} samples",
+        direction
+    );
+    quote! {
+        return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+            #message,
+            stringify!(#processor),
+            #port,
+            <#ty as raug::signal::Signal>::signal_type(),
+            inputs.block_size(),
+            #buffer.signal_type(),
+            #buffer.len(),
+        )))
+    }
+}
+
+/// Returns early with an error if the processor was given the wrong number of inputs.
+pub fn check_input_count(processor: &syn::Ident, input_count: usize) -> proc_macro2::TokenStream {
+    quote! {
+        if inputs.inputs.len() != #input_count {
+            return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                "{}: expected {} inputs, got {}",
+                stringify!(#processor),
+                #input_count,
+                inputs.inputs.len(),
+            )));
+        }
+    }
+}
+
+fn output_count_mismatch(processor: &syn::Ident, output_count: usize) -> proc_macro2::TokenStream {
+    quote! {
+        return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+            "{}: expected {} outputs, got {}",
+            stringify!(#processor),
+            #output_count,
+            outputs.outputs.len(),
+        )))
+    }
+}
+
+/// Returns early with an error if the processor was given the wrong number of outputs.
+pub fn check_output_count(processor: &syn::Ident, output_count: usize) -> proc_macro2::TokenStream {
+    let mismatch = output_count_mismatch(processor, output_count);
+    quote! {
+        if outputs.outputs.len() != #output_count {
+            #mismatch;
+        }
+    }
+}
+
+/// Splits `outputs` into one `&mut AnyBuffer` binding per port without any `unsafe`, returning
+/// an error if the processor was given the wrong number of outputs.
+pub fn split_outputs(processor: &syn::Ident, ports: &[syn::Ident]) -> proc_macro2::TokenStream {
+    let mismatch = output_count_mismatch(processor, ports.len());
+    quote! {
+        let [#(#ports),*] = &mut *outputs.outputs else {
+            #mismatch;
+        };
+    }
+}
+
+/// Binds `port` to the input at `index` as `&Option<&[T]>`, returning an error if a buffer is
+/// connected but has the wrong type or is shorter than the block.
+pub fn get_input(
+    processor: &syn::Ident,
+    port: &syn::Ident,
+    ty: &syn::Type,
+    index: usize,
+) -> proc_macro2::TokenStream {
+    let mismatch = buffer_mismatch(
+        processor,
+        "input",
+        &quote! { stringify!(#port) },
+        ty,
+        &quote! { buffer },
+    );
+    quote! {
+        let #port = &inputs.input_as::<#ty>(#index);
+        if let Some(Some(buffer)) = inputs.inputs.get(#index) {
+            if !matches!(#port, Some(inp) if inp.len() >= inputs.block_size()) {
+                #mismatch;
+            }
+        }
+    }
+}
+
+/// An expression evaluating to the `&mut T` slot at `__i` of the output `buffer`, returning an
+/// error if it has the wrong type or is shorter than the block.
+pub fn output_slot(
+    processor: &syn::Ident,
+    port: proc_macro2::TokenStream,
+    ty: &syn::Type,
+    buffer: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mismatch = buffer_mismatch(processor, "output", &port, ty, &buffer);
+    quote! {
+        match #buffer.get_mut_as::<#ty>(__i) {
+            Some(slot) => slot,
+            None => #mismatch,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use quote::{format_ident, quote};
+
+use super::{
+    buffers::{check_input_count, get_input, output_slot, split_outputs},
+    graph_hooks,
+    guard::{flush_denormals, guard_output, GuardNan},
+    is_f32, ProcessorArg,
+};
+
+pub struct ChannelsOptions {
+    pub channels: usize,
+    pub span: proc_macro2::Span,
+}
+
+/// The arguments of the processor function, as analyzed by `processor_attribute`.
+pub struct ChannelsArgs<'a> {
+    pub state: &'a [ProcessorArg],
+    pub shared_state: &'a [syn::Ident],
+    pub input: &'a [ProcessorArg],
+    pub output: &'a [ProcessorArg],
+    pub returned: &'a [ProcessorArg],
+    pub returns_tuple: bool,
+    pub context: &'a [(syn::Ident, syn::Pat, syn::Type)],
+    pub flush_denormals: Option<proc_macro2::Span>,
+    pub guard_nan: Option<GuardNan>,
+}
+
+fn channel_suffixes(channels: usize) -> Vec<String> {
+    if channels == 2 {
+        vec!["l".to_string(), "r".to_string()]
+    } else {
+        (0..channels).map(|channel| channel.to_string()).collect()
+    }
+}
+
+/// Generates a `<Name>Multi` processor that runs the processor once per channel, with a port per
+/// channel for each `f32` input and output, and a copy of each non-shared state field per channel.
+pub fn channels_processor(
+    options: &ChannelsOptions,
+    struct_name: &syn::Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    derives: &[syn::Path],
+    args: ChannelsArgs,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !generics.params.is_empty() {
+        return Err(syn::Error::new(
+            options.span,
+            "Generic processors cannot be expanded with `channels`",
+        ));
+    }
+
+    let channels = options.channels;
+    let suffixes = channel_suffixes(channels);
+    let multi_name = format_ident!("{}Multi", struct_name);
+
+    let mut struct_fields = vec![];
+    let mut input_spec = vec![];
+    let mut output_spec = vec![];
+    let mut create_output_buffers = vec![];
+    let mut get_inputs = vec![];
+    let mut input_ports = vec![];
+    let mut output_ports = vec![];
+    let mut assign_inputs = vec![];
+    let mut assign_outputs = vec![];
+    let mut node_inputs = vec![];
+    let mut node_fn_args = vec![];
+    let mut advance_time = quote! {};
+    let mut allocate_fn = quote! {};
+    // The arguments to `process_sample` for each channel.
+    let mut call_args = vec![vec![]; channels];
+    let mut returned_slots = vec![vec![]; channels];
+    // The `f32` output slots to check after each channel's `process_sample` call.
+    let mut guarded_slots = vec![vec![]; channels];
+
+    for ProcessorArg { name, ty, .. } in args.state.iter() {
+        if args.shared_state.contains(name) {
+            struct_fields.push(quote! {
+                pub #name: #ty,
+            });
+            for call_args in call_args.iter_mut() {
+                call_args.push(quote! { &mut self.#name, });
+            }
+        } else {
+            struct_fields.push(quote! {
+                pub #name: [#ty; #channels],
+            });
+            for (channel, call_args) in call_args.iter_mut().enumerate() {
+                call_args.push(quote! { &mut self.#name[#channel], });
+            }
+        }
+    }
+
+    let mut input_index = 0usize;
+    for ProcessorArg {
+        name, ty, by_value, ..
+    } in args.input.iter()
+    {
+        let reference = if *by_value {
+            quote! {}
+        } else {
+            quote! { & }
+        };
+        let ports = if is_f32(ty) {
+            struct_fields.push(quote! {
+                pub #name: [#ty; #channels],
+            });
+            for (channel, call_args) in call_args.iter_mut().enumerate() {
+                call_args.push(quote! { #reference self.#name[#channel], });
+            }
+            suffixes
+                .iter()
+                .enumerate()
+                .map(|(channel, suffix)| {
+                    (
+                        format_ident!("{}_{}", name, suffix),
+                        quote! { self.#name[#channel] },
+                    )
+                })
+                .collect::<Vec<_>>()
+        } else {
+            struct_fields.push(quote! {
+                pub #name: #ty,
+            });
+            for call_args in call_args.iter_mut() {
+                call_args.push(quote! { #reference self.#name, });
+            }
+            vec![(name.clone(), quote! { self.#name })]
+        };
+        for (port, field) in ports {
+            input_spec.push(quote! {
+                raug::processor::io::SignalSpec::new(stringify!(#port), <#ty as raug::signal::Signal>::signal_type())
+            });
+            get_inputs.push(get_input(&multi_name, &port, ty, input_index));
+            assign_inputs.push(quote! {
+                if let Some(#port) = #port.map(|inp| &inp[__i]) {
+                    Clone::clone_from(&mut #field, #port);
+                }
+            });
+            node_inputs.push(quote! { #port });
+            input_ports.push(port.clone());
+            node_fn_args.push(quote! {
+                #port: impl raug::graph::node::IntoOutputOpt,
+            });
+            input_index += 1;
+        }
+    }
+
+    for (arg_index, ProcessorArg { name, ty, .. }) in
+        args.output.iter().chain(args.returned.iter()).enumerate()
+    {
+        let ports = if is_f32(ty) {
+            suffixes
+                .iter()
+                .map(|suffix| format_ident!("{}_{}", name, suffix))
+                .collect::<Vec<_>>()
+        } else {
+            vec![name.clone()]
+        };
+        for channel in 0..channels {
+            // Non-`f32` outputs are shared by all channels, so the last channel's value wins.
+            let port = &ports[channel.min(ports.len() - 1)];
+            if is_f32(ty) && args.guard_nan.is_some() {
+                guarded_slots[channel].push(port.clone());
+            }
+            if arg_index < args.output.len() {
+                call_args[channel].push(quote! { #port, });
+            } else {
+                returned_slots[channel].push(port.clone());
+            }
+        }
+        for port in ports.iter() {
+            output_spec.push(quote! {
+                raug::processor::io::SignalSpec::new(stringify!(#port), <#ty as raug::signal::Signal>::signal_type())
+            });
+            create_output_buffers.push(quote! {
+                raug::signal::type_erased::AnyBuffer::zeros::<#ty>(size)
+            });
+            output_ports.push(port.clone());
+            let slot = output_slot(
+                &multi_name,
+                quote! { stringify!(#port) },
+                ty,
+                quote! { #port },
+            );
+            assign_outputs.push(quote! {
+                let #port = #slot;
+            });
+        }
+    }
+
+    for (kind, _, ty) in args.context.iter() {
+        let value = if kind == "sample_index" {
+            quote! { __i as #ty, }
+        } else if kind == "block_size" {
+            quote! { inputs.block_size() as #ty, }
+        } else {
+            struct_fields.push(quote! {
+                #[doc(hidden)]
+                pub __elapsed_samples: u64,
+            });
+            allocate_fn = quote! {
+                fn allocate(&mut self, _sample_rate: f32, _block_size: usize) {
+                    self.__elapsed_samples = 0;
+                }
+            };
+            advance_time = quote! {
+                self.__elapsed_samples += inputs.block_size() as u64;
+            };
+            quote! {
+                ((self.__elapsed_samples + __i as u64) as f64 / inputs.env.sample_rate as f64) as #ty,
+            }
+        };
+        for call_args in call_args.iter_mut() {
+            call_args.push(value.clone());
+        }
+    }
+
+    let mut process_channels = vec![];
+    for ((call_args, slots), guarded) in call_args
+        .iter()
+        .zip(returned_slots.iter())
+        .zip(guarded_slots.iter())
+    {
+        let call = quote! {
+            #struct_name::process_sample(inputs.env, #(#call_args)*)?
+        };
+        process_channels.push(match slots.as_slice() {
+            [] => quote! { #call; },
+            [slot] if !args.returns_tuple => quote! { *#slot = #call; },
+            slots => {
+                let values = (0..slots.len())
+                    .map(|i| format_ident!("__value{}", i))
+                    .collect::<Vec<_>>();
+                quote! {
+                    let (#(#values,)*) = #call;
+                    #(*#slots = #values;)*
+                }
+            }
+        });
+        if let Some(guard) = args.guard_nan {
+            process_channels.extend(
+                guarded
+                    .iter()
+                    .map(|slot| guard_output(guard, &multi_name, slot, slot)),
+            );
+        }
+    }
+
+    let check_input_count = check_input_count(&multi_name, input_spec.len());
+    let split_outputs = split_outputs(&multi_name, &output_ports);
+    let graph_hooks = graph_hooks(
+        &multi_name,
+        &syn::Generics::default(),
+        &input_ports,
+        &output_ports,
+    );
+
+    let flush_denormals = if let Some(span) = args.flush_denormals {
+        flush_denormals(span)
+    } else {
+        quote! {}
+    };
+
+    let doc = format!("A {}-channel version of [`{}`].", channels, struct_name);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(#(#derives),*)]
+        #[allow(missing_docs)]
+        #vis struct #multi_name {
+            #(#struct_fields)*
+        }
+
+        impl #multi_name {
+            #[doc = concat!("Adds a new ", stringify!(#multi_name), "node to the graph and connects its inputs.")]
+            #[allow(unused)]
+            #[allow(clippy::too_many_arguments)]
+            #[track_caller]
+            #vis fn node(self, graph: &raug::graph::Graph, #(#node_fn_args)*) -> raug::graph::node::Node {
+                use raug::graph::node::IntoOutputOpt;
+                let node = graph.node(self);
+                let mut input_index = 0;
+                #(
+                    if let Some(input) = #node_inputs.into_output_opt(graph) {
+                        node.input(input_index).connect(input);
+                    }
+                    input_index += 1;
+                )*
+                node
+            }
+        }
+
+        #graph_hooks
+
+        impl raug::processor::Processor for #multi_name {
+            fn name(&self) -> &str {
+                stringify!(#multi_name)
+            }
+
+            fn input_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                vec![#(#input_spec),*]
+            }
+
+            fn output_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                vec![#(#output_spec),*]
+            }
+
+            fn create_output_buffers(&self, size: usize) -> Vec<raug::signal::type_erased::AnyBuffer> {
+                vec![#(#create_output_buffers),*]
+            }
+
+            #allocate_fn
+
+            #[track_caller]
+            fn process(&mut self, inputs: raug::processor::io::ProcessorInputs, outputs: raug::processor::io::ProcessorOutputs) -> Result<(), raug::processor::ProcessorError> {
+                #check_input_count
+
+                #(#get_inputs)*
+                #split_outputs
+                #flush_denormals
+
+                for __i in 0..inputs.block_size() {
+                    #(#assign_inputs)*
+                    #(#assign_outputs)*
+                    #(#process_channels)*
+                }
+
+                #advance_time
+
+                Ok(())
+            }
+        }
+    })
+}
@@ -0,0 +1,185 @@
+use quote::{format_ident, quote};
+
+use super::{is_f32, ProcessorArg};
+
+fn is_bool(ty: &syn::Type) -> bool {
+    matches!(ty, syn::Type::Path(type_path) if type_path.path.is_ident("bool"))
+}
+
+/// Generates a `fuzz_process` method that drives the processor with blocks, sample rates and
+/// input samples derived from arbitrary bytes, panicking on errors or non-finite `f32` outputs.
+///
+/// The first byte picks the mode of the whole run. In bounded mode, every `f32` sample is within
+/// `-1.0..=1.0` and the outputs must be finite. In raw mode, half the `f32` samples are raw bit
+/// patterns, so NaN, infinities, denormals and large values reach the processor, and only errors
+/// are checked.
+pub fn fuzz_impl(
+    struct_name: &syn::Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    input_tys: &[&syn::Type],
+    outputs: &[&ProcessorArg],
+) -> proc_macro2::TokenStream {
+    let (ig, tg, wc) = generics.split_for_impl();
+
+    // Only `f32` and `bool` inputs can be generated; other inputs are left unconnected.
+    let mut fill_inputs = vec![];
+    let mut connected = vec![];
+    for (index, ty) in input_tys.iter().enumerate() {
+        let buffer = format_ident!("__input{}", index);
+        let sample = if is_f32(ty) {
+            quote! { bytes.sample() }
+        } else if is_bool(ty) {
+            quote! { bytes.next() % 2 == 0 }
+        } else {
+            connected.push(quote! { None });
+            continue;
+        };
+        fill_inputs.push(quote! {
+            let #buffer = if bytes.next() % 8 == 0 {
+                None
+            } else {
+                let mut buffer = raug::signal::type_erased::AnyBuffer::zeros::<#ty>(block_size);
+                if let Some(buffer) = buffer.as_mut_slice::<#ty>() {
+                    for sample in buffer.iter_mut() {
+                        *sample = #sample;
+                    }
+                }
+                Some(buffer)
+            };
+        });
+        connected.push(quote! { #buffer.as_ref() });
+    }
+
+    let mut check_outputs = vec![];
+    for (index, ProcessorArg { name, ty, .. }) in outputs.iter().enumerate() {
+        if !is_f32(ty) {
+            continue;
+        }
+        check_outputs.push(quote! {
+            let Some(output) = outputs[#index].as_slice::<f32>().and_then(|output| output.get(..block_size)) else {
+                panic!("{} wrote an invalid buffer to output `{}`", stringify!(#struct_name), stringify!(#name));
+            };
+            if let Some((i, value)) = output
+                .iter()
+                .enumerate()
+                .find(|(_, value)| !bytes.raw_bits && !value.is_finite())
+            {
+                panic!(
+                    "{} produced {} at sample {} of output `{}` (block {}, block size {}, sample rate {})",
+                    stringify!(#struct_name),
+                    value,
+                    i,
+                    stringify!(#name),
+                    block,
+                    block_size,
+                    sample_rate,
+                );
+            }
+        });
+    }
+
+    quote! {
+        impl #ig #struct_name #tg #wc {
+            #[doc = "Processes blocks of random sizes, sample rates and input samples derived from `data`."]
+            #[doc = ""]
+            #[doc = "The first byte of `data` picks whether `f32` inputs stay within `-1.0..=1.0`, or also"]
+            #[doc = "include raw bit patterns like NaN, infinities and denormals. Panics if processing fails,"]
+            #[doc = "or if an `f32` output isn't finite while the inputs are bounded. Suitable as the body of"]
+            #[doc = "a `cargo fuzz` target or a property test over arbitrary bytes."]
+            #[allow(unused)]
+            #vis fn fuzz_process(&mut self, data: &[u8]) {
+                use raug::processor::Processor;
+
+                const MAX_BLOCK_SIZE: usize = 2048;
+
+                struct Bytes<'a> {
+                    data: &'a [u8],
+                    // Whether `f32` samples may be raw bit patterns instead of within `-1.0..=1.0`.
+                    raw_bits: bool,
+                }
+
+                impl Bytes<'_> {
+                    fn next(&mut self) -> u8 {
+                        match self.data.split_first() {
+                            Some((byte, rest)) => {
+                                self.data = rest;
+                                *byte
+                            }
+                            None => 0,
+                        }
+                    }
+
+                    fn next_u16(&mut self) -> u16 {
+                        u16::from_le_bytes([self.next(), self.next()])
+                    }
+
+                    fn next_u32(&mut self) -> u32 {
+                        u32::from_le_bytes([self.next(), self.next(), self.next(), self.next()])
+                    }
+
+                    fn sample(&mut self) -> f32 {
+                        if self.raw_bits && self.next() % 2 == 0 {
+                            f32::from_bits(self.next_u32())
+                        } else {
+                            self.next_u16() as f32 / u16::MAX as f32 * 2.0 - 1.0
+                        }
+                    }
+
+                    fn sample_rate(&mut self) -> f32 {
+                        [8_000.0, 22_050.0, 44_100.0, 48_000.0, 96_000.0, 192_000.0][self.next() as usize % 6]
+                    }
+
+                    fn block_size(&mut self, max: usize) -> usize {
+                        1 + self.next_u16() as usize % max
+                    }
+                }
+
+                let mut bytes = Bytes {
+                    data,
+                    raw_bits: false,
+                };
+                // Empty or zeroed data runs in bounded mode.
+                bytes.raw_bits = bytes.next() % 4 == 3;
+                let mut sample_rate = bytes.sample_rate();
+                let mut max_block_size = bytes.block_size(MAX_BLOCK_SIZE);
+                self.allocate(sample_rate, max_block_size);
+
+                let input_spec = self.input_spec();
+                let output_spec = self.output_spec();
+                let mut outputs = self.create_output_buffers(MAX_BLOCK_SIZE);
+                let blocks = 1 + bytes.next() as usize % 16;
+                for block in 0..blocks {
+                    if bytes.next() % 4 == 0 {
+                        sample_rate = bytes.sample_rate();
+                        max_block_size = bytes.block_size(MAX_BLOCK_SIZE);
+                        self.resize_buffers(sample_rate, max_block_size);
+                    }
+                    let block_size = bytes.block_size(max_block_size);
+
+                    #(#fill_inputs)*
+                    let env = raug::processor::io::ProcEnv {
+                        sample_rate,
+                        block_size,
+                        mode: raug::processor::io::ProcessMode::Block,
+                    };
+                    if let Err(err) = self.process(
+                        raug::processor::io::ProcessorInputs::new(&input_spec, &[#(#connected),*], env),
+                        raug::processor::io::ProcessorOutputs::new(&output_spec, &mut outputs, raug::processor::io::ProcessMode::Block),
+                    ) {
+                        panic!(
+                            "{} failed on block {} (block size {}, sample rate {}): {:?}",
+                            stringify!(#struct_name),
+                            block,
+                            block_size,
+                            sample_rate,
+                            err,
+                        );
+                    }
+
+                    #(#check_outputs)*
+                }
+            }
+        }
+    }
+}
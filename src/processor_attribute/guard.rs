@@ -0,0 +1,90 @@
+use quote::{quote, quote_spanned};
+
+/// What to do when a processor writes a NaN or infinite value to an `f32` output.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GuardNan {
+    Zero,
+    Error,
+}
+
+pub fn parse_guard_nan(value: &syn::Expr) -> syn::Result<GuardNan> {
+    if let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit),
+        ..
+    }) = value
+    {
+        match lit.value().as_str() {
+            "zero" => return Ok(GuardNan::Zero),
+            "error" => return Ok(GuardNan::Error),
+            _ => {}
+        }
+    }
+    Err(syn::Error::new_spanned(
+        value.clone(),
+        "Expected \"zero\" or \"error\" for `guard_nan`",
+    ))
+}
+
+/// Sets the FTZ and DAZ flags until the end of the enclosing scope, restoring the previous
+/// flags when dropped (including on early returns). Does nothing on other architectures.
+///
+/// The MXCSR intrinsics are `unsafe` and there's no safe alternative, so this is the only
+/// generated code that uses `unsafe`. The `unsafe` blocks are spanned on the `flush_denormals`
+/// flag at `span`, so in a crate that forbids `unsafe` code the resulting error points at it.
+pub fn flush_denormals(span: proc_macro2::Span) -> proc_macro2::TokenStream {
+    let restore = quote_spanned! {span=>
+        // SAFETY: SSE is always available on x86_64, and we're restoring the previous flags.
+        unsafe { std::arch::x86_64::_mm_setcsr(self.0) }
+    };
+    let set = quote_spanned! {span=>
+        // Flush-to-zero (bit 15) and denormals-are-zero (bit 6).
+        // SAFETY: SSE is always available on x86_64, and the flags only affect denormal handling.
+        unsafe {
+            let csr = std::arch::x86_64::_mm_getcsr();
+            std::arch::x86_64::_mm_setcsr(csr | 0x8040);
+            FlushDenormals(csr)
+        }
+    };
+    quote! {
+        #[cfg(target_arch = "x86_64")]
+        #[allow(deprecated)]
+        let __flush_denormals = {
+            struct FlushDenormals(u32);
+
+            impl Drop for FlushDenormals {
+                fn drop(&mut self) {
+                    #restore;
+                }
+            }
+
+            #set
+        };
+    }
+}
+
+/// Checks the `f32` output slot `slot` after a call to `process_sample`, either zeroing it or
+/// returning an error naming the processor and port if it isn't finite.
+pub fn guard_output(
+    guard: GuardNan,
+    struct_name: &syn::Ident,
+    port: &syn::Ident,
+    slot: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    match guard {
+        GuardNan::Zero => quote! {
+            if !#slot.is_finite() {
+                *#slot = 0.0;
+            }
+        },
+        GuardNan::Error => quote! {
+            if !#slot.is_finite() {
+                return Err(raug::processor::ProcessorError::ProcessorSpecific(format!(
+                    "{} produced a non-finite value ({}) on output `{}`",
+                    stringify!(#struct_name),
+                    #slot,
+                    stringify!(#port),
+                )));
+            }
+        },
+    }
+}
@@ -0,0 +1,75 @@
+use quote::quote;
+
+/// Even-indexed taps of a 31-tap Blackman-windowed halfband lowpass used for oversampling.
+/// The odd-indexed taps are all zero except for the center tap, which is 0.5.
+const HALFBAND_TAPS: [f32; 16] = [
+    -7.463_903e-5,
+    8.539_396e-4,
+    -3.228_999_7e-3,
+    8.789_360_5e-3,
+    -2.017_080_4e-2,
+    4.246_806_4e-2,
+    -9.191_101e-2,
+    3.132_741e-1,
+    3.132_741e-1,
+    -9.191_101e-2,
+    4.246_806_4e-2,
+    -2.017_080_4e-2,
+    8.789_360_5e-3,
+    -3.228_999_7e-3,
+    8.539_396e-4,
+    -7.463_903e-5,
+];
+
+/// Checks that every port of an oversampled processor is `f32`, since other signals can't be
+/// resampled.
+pub fn check_oversample_ports<'a>(
+    ports: impl IntoIterator<Item = &'a syn::Type>,
+) -> Option<syn::Error> {
+    let mut errors = None;
+    for ty in ports.into_iter().filter(|ty| !super::is_f32(ty)) {
+        super::push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                ty.clone(),
+                "`oversample` requires all inputs and outputs to be `f32`",
+            ),
+        );
+    }
+    errors
+}
+
+/// Generates the halfband filter shared by the oversampled ports of a processor, as private
+/// associated items so they're emitted once per processor instead of in `process`.
+///
+/// `__halfband_upsample` turns one sample into two, and `__halfband_downsample` turns two samples
+/// into one. Each stage of a 4x or 8x resampler keeps its own filter state.
+pub fn halfband_impl(
+    struct_name: &syn::Ident,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let (ig, tg, wc) = generics.split_for_impl();
+    let halfband_taps = HALFBAND_TAPS.iter();
+    quote! {
+        #[allow(dead_code)]
+        impl #ig #struct_name #tg #wc {
+            const __HALFBAND_TAPS: [f32; 16] = [#(#halfband_taps),*];
+
+            fn __halfband_upsample(state: &mut [f32; 16], x: f32) -> [f32; 2] {
+                state.copy_within(0..15, 1);
+                state[0] = x;
+                let even = Self::__HALFBAND_TAPS.iter().zip(state.iter()).map(|(h, s)| h * s).sum::<f32>();
+                [2.0 * even, state[7]]
+            }
+
+            fn __halfband_downsample(state: &mut [f32; 24], a: f32, b: f32) -> f32 {
+                state.copy_within(0..15, 1);
+                state[0] = b;
+                state.copy_within(16..23, 17);
+                state[16] = a;
+                let even = Self::__HALFBAND_TAPS.iter().zip(state[..16].iter()).map(|(h, s)| h * s).sum::<f32>();
+                even + 0.5 * state[23]
+            }
+        }
+    }
+}
@@ -0,0 +1,507 @@
+use quote::{format_ident, quote};
+
+use super::{
+    buffers::{check_input_count, check_output_count, get_input, output_slot},
+    graph_hooks, is_f32, push_error, ProcessorArg,
+};
+
+/// How a new note picks a voice when all voices are busy.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VoiceStealing {
+    Oldest,
+    Quietest,
+    RoundRobin,
+}
+
+pub struct PolyOptions {
+    pub voices: usize,
+    pub stealing: VoiceStealing,
+    pub per_voice_outputs: bool,
+    pub span: proc_macro2::Span,
+}
+
+impl PolyOptions {
+    pub fn new(voices: usize, span: proc_macro2::Span) -> Self {
+        Self {
+            voices,
+            stealing: VoiceStealing::Oldest,
+            per_voice_outputs: false,
+            span,
+        }
+    }
+}
+
+pub fn parse_voice_stealing(value: &syn::Expr) -> syn::Result<VoiceStealing> {
+    if let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit),
+        ..
+    }) = value
+    {
+        match lit.value().as_str() {
+            "oldest" => return Ok(VoiceStealing::Oldest),
+            "quietest" => return Ok(VoiceStealing::Quietest),
+            "round_robin" => return Ok(VoiceStealing::RoundRobin),
+            _ => {}
+        }
+    }
+    Err(syn::Error::new_spanned(
+        value.clone(),
+        "Expected \"oldest\", \"quietest\" or \"round_robin\" for `voice_stealing`",
+    ))
+}
+
+pub fn parse_poly_outputs(value: &syn::Expr) -> syn::Result<bool> {
+    if let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Str(lit),
+        ..
+    }) = value
+    {
+        match lit.value().as_str() {
+            "sum" => return Ok(false),
+            "per_voice" => return Ok(true),
+            _ => {}
+        }
+    }
+    Err(syn::Error::new_spanned(
+        value.clone(),
+        "Expected \"sum\" or \"per_voice\" for `poly_outputs`",
+    ))
+}
+
+/// Generates a `<Name>Poly` processor that allocates notes from its `note` and `gate` inputs to
+/// a fixed number of voices of the monophonic processor, and sums or exposes their outputs.
+///
+/// There is a single `note` and `gate` stream, so only one voice is gated at a time: a new note
+/// releases the previous one, which keeps ringing in its own voice until that voice is stolen.
+/// The stealing policy picks among the released voices, using the peak of each voice's outputs
+/// over the previous block for `quietest`.
+pub fn poly_processor(
+    options: &PolyOptions,
+    struct_name: &syn::Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    input: &[ProcessorArg],
+    outputs: &[&ProcessorArg],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = None;
+
+    if !generics.params.is_empty() {
+        push_error(
+            &mut errors,
+            syn::Error::new(
+                options.span,
+                "Generic processors cannot be expanded with `poly`",
+            ),
+        );
+    }
+
+    let note = input.iter().position(|arg| arg.name == "note");
+    let gate = input.iter().position(|arg| arg.name == "gate");
+    let (Some(note), Some(gate)) = (note, gate) else {
+        push_error(
+            &mut errors,
+            syn::Error::new(
+                options.span,
+                "`poly` requires `#[input] note` and `#[input] gate` arguments",
+            ),
+        );
+        return Err(errors.unwrap());
+    };
+
+    let gate_ty = &input[gate].ty;
+    let gate_is_bool =
+        matches!(gate_ty, syn::Type::Path(type_path) if type_path.path.is_ident("bool"));
+    if !gate_is_bool && !is_f32(gate_ty) {
+        push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                gate_ty.clone(),
+                "The `gate` input must be `bool` or `f32` for `poly`",
+            ),
+        );
+    }
+
+    if !options.per_voice_outputs {
+        for output in outputs.iter().filter(|output| !is_f32(&output.ty)) {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    output.ty.clone(),
+                    "Only `f32` outputs can be summed across voices; use `poly_outputs = \"per_voice\"`",
+                ),
+            );
+        }
+    }
+
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    let voices = options.voices;
+    let poly_name = format_ident!("{}Poly", struct_name);
+    let note_ty = &input[note].ty;
+    let (gate_on, gate_off) = if gate_is_bool {
+        (quote! { true }, quote! { false })
+    } else {
+        (quote! { 1.0 }, quote! { 0.0 })
+    };
+    let is_gate_on = if gate_is_bool {
+        quote! { self.gate }
+    } else {
+        quote! { self.gate > 0.0 }
+    };
+
+    let mut input_fields = vec![];
+    let mut input_init = vec![];
+    let mut get_inputs = vec![];
+    let mut assign_inputs = vec![];
+    let mut share_inputs = vec![];
+    let mut voice_inputs = vec![];
+    let mut node_inputs = vec![];
+    let mut node_fn_args = vec![];
+    for (index, arg) in input.iter().enumerate() {
+        let ProcessorArg { name, ty, .. } = arg;
+        input_fields.push(quote! {
+            pub #name: #ty,
+        });
+        input_init.push(quote! {
+            #name: Clone::clone(&first.#name),
+        });
+        get_inputs.push(get_input(&poly_name, name, ty, index));
+        assign_inputs.push(quote! {
+            if let Some(#name) = #name.map(|inp| &inp[__i]) {
+                Clone::clone_from(&mut self.#name, #name);
+            }
+        });
+        if index == note {
+            voice_inputs.push(quote! { Some(&self.note_buffers[voice]) });
+        } else if index == gate {
+            voice_inputs.push(quote! { Some(&self.gate_buffers[voice]) });
+        } else {
+            share_inputs.push(quote! {
+                Clone::clone_from(&mut self.voices[voice].#name, &self.#name);
+            });
+            voice_inputs.push(quote! { inputs.inputs[#index] });
+        }
+        node_inputs.push(quote! { #name });
+        node_fn_args.push(quote! {
+            #name: impl raug::graph::node::IntoOutputOpt,
+        });
+    }
+
+    let output_count = outputs.len();
+    let mut output_spec = vec![];
+    let mut write_outputs = vec![];
+    let mut voice_levels = vec![];
+    for (index, arg) in outputs.iter().enumerate() {
+        let ProcessorArg { name, ty, .. } = arg;
+        if is_f32(ty) {
+            voice_levels.push(quote! {
+                .max(
+                    self.output_buffers[voice][#index]
+                        .as_slice::<f32>()
+                        .map_or(0.0, |block| block.iter().take(block_size).fold(0.0f32, |peak, x| peak.max(x.abs())))
+                )
+            });
+        }
+        let voice_slot = output_slot(
+            &poly_name,
+            quote! { format_args!("{} (voice {})", stringify!(#name), voice) },
+            ty,
+            quote! { self.output_buffers[voice][#index] },
+        );
+        if options.per_voice_outputs {
+            let slot = output_slot(
+                &poly_name,
+                quote! { format_args!("{}_{}", stringify!(#name), voice) },
+                ty,
+                quote! { outputs[voice * #output_count + #index] },
+            );
+            write_outputs.push(quote! {
+                for voice in 0..#voices {
+                    for __i in 0..block_size {
+                        let value = Clone::clone(#voice_slot);
+                        *#slot = value;
+                    }
+                }
+            });
+        } else {
+            output_spec.push(quote! {
+                raug::processor::io::SignalSpec::new(stringify!(#name), <#ty as raug::signal::Signal>::signal_type())
+            });
+            let slot = output_slot(
+                &poly_name,
+                quote! { stringify!(#name) },
+                ty,
+                quote! { outputs[#index] },
+            );
+            write_outputs.push(quote! {
+                for __i in 0..block_size {
+                    let mut sum = 0.0f32;
+                    for voice in 0..#voices {
+                        sum += *#voice_slot;
+                    }
+                    *#slot = sum;
+                }
+            });
+        }
+    }
+
+    // Per-voice outputs are laid out voice-major, i.e. all of the first voice's outputs come first.
+    if options.per_voice_outputs {
+        for voice in 0..voices {
+            for ProcessorArg { name, ty, .. } in outputs.iter() {
+                let port = format!("{}_{}", name, voice);
+                output_spec.push(quote! {
+                    raug::processor::io::SignalSpec::new(#port, <#ty as raug::signal::Signal>::signal_type())
+                });
+            }
+        }
+    }
+
+    let output_ports = if options.per_voice_outputs {
+        (0..voices)
+            .flat_map(|voice| {
+                outputs
+                    .iter()
+                    .map(move |arg| format_ident!("{}_{}", arg.name, voice))
+            })
+            .collect::<Vec<_>>()
+    } else {
+        outputs.iter().map(|arg| arg.name.clone()).collect()
+    };
+    let graph_hooks = graph_hooks(
+        &poly_name,
+        &syn::Generics::default(),
+        &input.iter().map(|arg| arg.name.clone()).collect::<Vec<_>>(),
+        &output_ports,
+    );
+
+    let create_output_buffers = if options.per_voice_outputs {
+        quote! {
+            (0..#voices)
+                .flat_map(|_| raug::processor::Processor::create_output_buffers(&self.voices[0], size))
+                .collect()
+        }
+    } else {
+        quote! {
+            raug::processor::Processor::create_output_buffers(&self.voices[0], size)
+        }
+    };
+
+    let steal_voice = match options.stealing {
+        VoiceStealing::RoundRobin => quote! {
+            let voice = self.next_voice;
+            self.next_voice = (voice + 1) % #voices;
+            voice
+        },
+        VoiceStealing::Oldest => quote! {
+            (0..#voices)
+                .min_by_key(|&voice| (self.voice_gates[voice], self.voice_ages[voice]))
+                .unwrap_or(0)
+        },
+        VoiceStealing::Quietest => quote! {
+            (0..#voices)
+                .min_by(|&a, &b| {
+                    self.voice_gates[a]
+                        .cmp(&self.voice_gates[b])
+                        .then(self.voice_levels[a].total_cmp(&self.voice_levels[b]))
+                })
+                .unwrap_or(0)
+        },
+    };
+
+    let check_io_counts = [
+        check_input_count(&poly_name, input.len()),
+        check_output_count(&poly_name, output_spec.len()),
+    ];
+
+    let doc = format!(
+        "A polyphonic version of [`{}`] with {} voices.\n\n\
+        Notes are played one at a time from the `note` and `gate` inputs. Each new note gets its \
+        own voice, so the release tails of previous notes keep ringing until their voices are \
+        reused.",
+        struct_name, voices
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[allow(missing_docs)]
+        #vis struct #poly_name {
+            pub voices: Vec<#struct_name>,
+            #(#input_fields)*
+            voice_notes: Vec<#note_ty>,
+            voice_gates: Vec<bool>,
+            voice_ages: Vec<u64>,
+            voice_levels: Vec<f32>,
+            note_buffers: Vec<raug::signal::type_erased::AnyBuffer>,
+            gate_buffers: Vec<raug::signal::type_erased::AnyBuffer>,
+            output_buffers: Vec<Vec<raug::signal::type_erased::AnyBuffer>>,
+            buffer_size: usize,
+            voice_input_spec: Vec<raug::processor::io::SignalSpec>,
+            voice_output_spec: Vec<raug::processor::io::SignalSpec>,
+            active_voice: Option<usize>,
+            last_gate: bool,
+            last_note: #note_ty,
+            next_voice: usize,
+            note_count: u64,
+        }
+
+        impl #poly_name {
+            #[doc = "Creates a new polyphonic processor, calling `voice` to create each voice."]
+            pub fn new(mut voice: impl FnMut() -> #struct_name) -> Self {
+                let voices = (0..#voices).map(|_| voice()).collect::<Vec<_>>();
+                let first = &voices[0];
+                Self {
+                    #(#input_init)*
+                    voice_notes: vec![Clone::clone(&first.note); #voices],
+                    voice_gates: vec![false; #voices],
+                    voice_ages: vec![0; #voices],
+                    voice_levels: vec![0.0; #voices],
+                    note_buffers: vec![],
+                    gate_buffers: vec![],
+                    output_buffers: vec![],
+                    buffer_size: 0,
+                    voice_input_spec: raug::processor::Processor::input_spec(first),
+                    voice_output_spec: raug::processor::Processor::output_spec(first),
+                    active_voice: None,
+                    last_gate: false,
+                    last_note: Clone::clone(&first.note),
+                    next_voice: 0,
+                    // Unused voices have age 0, so they're picked before any released voice.
+                    note_count: 1,
+                    voices,
+                }
+            }
+
+            fn allocate_voice(&mut self) -> usize {
+                #steal_voice
+            }
+
+            fn allocate_voice_buffers(&mut self, block_size: usize) {
+                self.note_buffers = (0..#voices)
+                    .map(|_| raug::signal::type_erased::AnyBuffer::zeros::<#note_ty>(block_size))
+                    .collect();
+                self.gate_buffers = (0..#voices)
+                    .map(|_| raug::signal::type_erased::AnyBuffer::zeros::<#gate_ty>(block_size))
+                    .collect();
+                self.output_buffers = self
+                    .voices
+                    .iter()
+                    .map(|voice| raug::processor::Processor::create_output_buffers(voice, block_size))
+                    .collect();
+                self.buffer_size = block_size;
+            }
+
+            #[doc = concat!("Adds a new ", stringify!(#poly_name), "node to the graph and connects its inputs.")]
+            #[allow(unused)]
+            #[allow(clippy::too_many_arguments)]
+            #[track_caller]
+            #vis fn node(self, graph: &raug::graph::Graph, #(#node_fn_args)*) -> raug::graph::node::Node {
+                use raug::graph::node::IntoOutputOpt;
+                let node = graph.node(self);
+                let mut input_index = 0;
+                #(
+                    if let Some(input) = #node_inputs.into_output_opt(graph) {
+                        node.input(input_index).connect(input);
+                    }
+                    input_index += 1;
+                )*
+                node
+            }
+        }
+
+        #graph_hooks
+
+        impl raug::processor::Processor for #poly_name {
+            fn name(&self) -> &str {
+                stringify!(#poly_name)
+            }
+
+            fn input_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                self.voice_input_spec.clone()
+            }
+
+            fn output_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                vec![#(#output_spec),*]
+            }
+
+            fn create_output_buffers(&self, size: usize) -> Vec<raug::signal::type_erased::AnyBuffer> {
+                #create_output_buffers
+            }
+
+            fn allocate(&mut self, sample_rate: f32, block_size: usize) {
+                for voice in self.voices.iter_mut() {
+                    raug::processor::Processor::allocate(voice, sample_rate, block_size);
+                }
+                self.allocate_voice_buffers(block_size);
+            }
+
+            fn resize_buffers(&mut self, sample_rate: f32, block_size: usize) {
+                for voice in self.voices.iter_mut() {
+                    raug::processor::Processor::resize_buffers(voice, sample_rate, block_size);
+                }
+                self.allocate_voice_buffers(block_size);
+            }
+
+            #[track_caller]
+            fn process(&mut self, inputs: raug::processor::io::ProcessorInputs, outputs: raug::processor::io::ProcessorOutputs) -> Result<(), raug::processor::ProcessorError> {
+                #(#check_io_counts)*
+
+                let block_size = inputs.block_size();
+                if self.buffer_size != block_size {
+                    self.allocate_voice_buffers(block_size);
+                }
+
+                #(#get_inputs)*
+
+                for __i in 0..block_size {
+                    #(#assign_inputs)*
+
+                    let gate = #is_gate_on;
+                    let note_changed = self.note != self.last_note;
+                    if self.last_gate && (!gate || note_changed) {
+                        if let Some(voice) = self.active_voice.take() {
+                            self.voice_gates[voice] = false;
+                        }
+                    }
+                    if gate && (!self.last_gate || note_changed) {
+                        let voice = self.allocate_voice();
+                        self.voice_notes[voice] = Clone::clone(&self.note);
+                        self.voice_gates[voice] = true;
+                        self.voice_ages[voice] = self.note_count;
+                        self.note_count += 1;
+                        self.active_voice = Some(voice);
+                    }
+                    self.last_gate = gate;
+                    self.last_note = Clone::clone(&self.note);
+
+                    for voice in 0..#voices {
+                        if let Some(note) = self.note_buffers[voice].get_mut_as::<#note_ty>(__i) {
+                            *note = Clone::clone(&self.voice_notes[voice]);
+                        }
+                        if let Some(gate) = self.gate_buffers[voice].get_mut_as::<#gate_ty>(__i) {
+                            *gate = if self.voice_gates[voice] { #gate_on } else { #gate_off };
+                        }
+                    }
+                }
+
+                for voice in 0..#voices {
+                    #(#share_inputs)*
+                    let voice_inputs = [#(#voice_inputs),*];
+                    raug::processor::Processor::process(
+                        &mut self.voices[voice],
+                        raug::processor::io::ProcessorInputs::new(&self.voice_input_spec, &voice_inputs, inputs.env),
+                        raug::processor::io::ProcessorOutputs::new(&self.voice_output_spec, &mut self.output_buffers[voice], outputs.mode),
+                    )?;
+                    self.voice_levels[voice] = 0.0f32 #(#voice_levels)*;
+                }
+
+                let raug::processor::io::ProcessorOutputs { outputs, .. } = outputs;
+                #(#write_outputs)*
+
+                Ok(())
+            }
+        }
+    })
+}
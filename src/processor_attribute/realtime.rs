@@ -0,0 +1,196 @@
+use quote::{quote, quote_spanned};
+use syn::visit::Visit;
+
+use super::{push_error, ProcessorArg};
+
+/// Functions that allocate, matched against the last two segments of the called path.
+/// Constructors that don't allocate, like `Vec::new`, aren't included.
+const ALLOCATING_FNS: &[&str] = &[
+    "Vec::with_capacity",
+    "Box::new",
+    "String::from",
+    "String::with_capacity",
+    "Rc::new",
+    "Arc::new",
+];
+
+/// Macros that allocate or block on I/O.
+const ALLOCATING_MACROS: &[&str] = &[
+    "format", "vec", "println", "print", "eprintln", "eprint", "dbg",
+];
+
+/// Methods that usually allocate or block. Since the receiver's type isn't known, these are only
+/// reported as warnings.
+const BLOCKING_METHODS: &[&str] = &[
+    "lock",
+    "push",
+    "push_str",
+    "insert",
+    "to_string",
+    "to_vec",
+    "to_owned",
+    "collect",
+];
+
+/// Types whose `clone()` allocates.
+const HEAP_TYPES: &[&str] = &[
+    "Vec", "String", "Box", "Rc", "Arc", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "VecDeque",
+];
+
+struct RealtimeLint<'a> {
+    heap_args: Vec<&'a syn::Ident>,
+    /// The lint attributes of the processor function, applied to the warnings.
+    lint_attrs: Vec<&'a syn::Attribute>,
+    errors: Option<syn::Error>,
+    warnings: Vec<proc_macro2::TokenStream>,
+}
+
+impl RealtimeLint<'_> {
+    fn report(&mut self, tokens: impl quote::ToTokens, what: String) {
+        push_error(
+            &mut self.errors,
+            syn::Error::new_spanned(
+                tokens,
+                format!(
+                    "{} may allocate or block, which is not allowed in a `#[processor(realtime)]` body",
+                    what
+                ),
+            ),
+        );
+    }
+
+    /// Emits a warning at `span` by referring to a deprecated constant, since proc macros can't
+    /// emit warnings directly. It can be silenced with `#[allow(deprecated)]` on the function.
+    fn warn(&mut self, span: proc_macro2::Span, what: String) {
+        let note = format!(
+            "{} may allocate or block depending on its receiver, which is not allowed in a `#[processor(realtime)]` body",
+            what
+        );
+        let usage = quote_spanned! {span=> may_allocate_or_block };
+        let lint_attrs = &self.lint_attrs;
+        self.warnings.push(quote! {
+            #(#lint_attrs)*
+            const _: () = {
+                #[deprecated(note = #note)]
+                #[allow(non_upper_case_globals)]
+                const may_allocate_or_block: () = ();
+                #usage
+            };
+        });
+    }
+}
+
+impl<'ast> Visit<'ast> for RealtimeLint<'_> {
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(path) = &*call.func {
+            let segments = path
+                .path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>();
+            let name = segments[segments.len().saturating_sub(2)..].join("::");
+            if ALLOCATING_FNS.contains(&name.as_str()) {
+                self.report(&call.func, format!("`{}`", name));
+            }
+        }
+        syn::visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        let method = call.method.to_string();
+        if BLOCKING_METHODS.contains(&method.as_str()) {
+            self.warn(call.method.span(), format!("`.{}()`", method));
+        } else if method == "clone" {
+            if let syn::Expr::Path(path) = ungroup_expr(&call.receiver) {
+                if let Some(ident) = path.path.get_ident() {
+                    if self.heap_args.contains(&ident) {
+                        self.report(&call.method, format!("Cloning `{}`", ident));
+                    }
+                }
+            }
+        }
+        syn::visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        if let Some(name) = mac.path.segments.last().map(|segment| &segment.ident) {
+            if ALLOCATING_MACROS.contains(&name.to_string().as_str()) {
+                self.report(&mac.path, format!("`{}!`", name));
+            }
+        }
+        // Look inside function-like macros whose arguments are expressions, like `assert!(...)`.
+        if let Ok(args) = mac.parse_body_with(
+            syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+        ) {
+            for arg in args.iter() {
+                self.visit_expr(arg);
+            }
+        }
+    }
+
+    // Nested items aren't run on the audio thread unless called, and calls into them aren't
+    // followed anyway.
+    fn visit_item(&mut self, _item: &'ast syn::Item) {}
+}
+
+fn ungroup_expr(expr: &syn::Expr) -> &syn::Expr {
+    match expr {
+        syn::Expr::Paren(paren) => ungroup_expr(&paren.expr),
+        syn::Expr::Group(group) => ungroup_expr(&group.expr),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Deref(_),
+            expr,
+            ..
+        }) => ungroup_expr(expr),
+        expr => expr,
+    }
+}
+
+fn is_heap_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| HEAP_TYPES.contains(&segment.ident.to_string().as_str())),
+        syn::Type::Reference(reference) => is_heap_type(&reference.elem),
+        syn::Type::Group(group) => is_heap_type(&group.elem),
+        syn::Type::Paren(paren) => is_heap_type(&paren.elem),
+        _ => false,
+    }
+}
+
+/// Scans a processor body for known allocating or blocking calls, returning an error spanned on
+/// each definite one, and items emitting a warning on each call that only might allocate or block.
+pub fn check_realtime<'a>(
+    body: &syn::Block,
+    attrs: &'a [syn::Attribute],
+    args: impl IntoIterator<Item = &'a ProcessorArg>,
+) -> (Option<syn::Error>, proc_macro2::TokenStream) {
+    let heap_args = args
+        .into_iter()
+        .filter(|arg| is_heap_type(&arg.ty))
+        .filter_map(|arg| match &arg.pat {
+            syn::Pat::Ident(pat) => Some(&pat.ident),
+            _ => None,
+        })
+        .collect();
+    let lint_attrs = attrs
+        .iter()
+        .filter(|attr| {
+            ["allow", "warn", "deny", "forbid", "expect"]
+                .iter()
+                .any(|lint| attr.path().is_ident(lint))
+        })
+        .collect();
+    let mut lint = RealtimeLint {
+        heap_args,
+        lint_attrs,
+        errors: None,
+        warnings: vec![],
+    };
+    lint.visit_block(body);
+    let warnings = lint.warnings;
+    (lint.errors, quote! { #(#warnings)* })
+}
@@ -0,0 +1,321 @@
+use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse::Parser, punctuated::Punctuated};
+
+use super::{
+    arg_name, is_reference, parse_struct_option, port_indices, processor_attribute, push_error,
+    reference_elem, ungroup,
+};
+
+struct Port {
+    name: syn::Ident,
+    pat: syn::Pat,
+    ty: syn::Type,
+}
+
+/// Generates a passthrough processor with an input `from` and an output `to` per port, used as
+/// the inlet and outlet of a subgraph. It's emitted in the subgraph's hidden module, so it's only
+/// visible to the subgraph.
+fn passthrough(
+    func_name: syn::Ident,
+    ports: &[Port],
+    from: impl Fn(&syn::Ident) -> syn::Ident,
+    to: impl Fn(&syn::Ident) -> syn::Ident,
+) -> proc_macro2::TokenStream {
+    let inputs = ports
+        .iter()
+        .map(|port| from(&port.name))
+        .collect::<Vec<_>>();
+    let outputs = ports.iter().map(|port| to(&port.name)).collect::<Vec<_>>();
+    let tys = ports.iter().map(|port| &port.ty).collect::<Vec<_>>();
+    let (return_ty, body) = match (inputs.as_slice(), tys.as_slice()) {
+        ([input], [ty]) => (quote! { #ty }, quote! { Clone::clone(#input) }),
+        _ => (
+            quote! { (#(#tys),*) },
+            quote! { (#(Clone::clone(#inputs)),*) },
+        ),
+    };
+    let attr = quote! {
+        attr(doc(hidden)),
+        outputs(#(#outputs),*)
+    };
+    let item = quote! {
+        pub(super) fn #func_name(#(#[input] #inputs: &#tys),*) -> #return_ty {
+            #body
+        }
+    };
+    processor_attribute(attr.into(), item.into()).into()
+}
+
+pub fn subgraph_attribute(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated.parse(attr);
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+
+    let mut errors = None;
+    let mut extra_derives = vec![];
+    let mut extra_attrs = vec![];
+    match args {
+        Ok(args) => {
+            for arg in args {
+                let parsed = match &arg {
+                    syn::Meta::List(meta_list) => parse_struct_option(
+                        meta_list,
+                        &mut extra_derives,
+                        &mut extra_attrs,
+                        &mut errors,
+                    ),
+                    _ => false,
+                };
+                if !parsed {
+                    push_error(
+                        &mut errors,
+                        syn::Error::new_spanned(
+                            arg.path(),
+                            "Unknown attribute. Only `derive` and `attr` are supported.",
+                        ),
+                    );
+                }
+            }
+        }
+        Err(err) => push_error(&mut errors, err),
+    }
+
+    if !item.sig.generics.params.is_empty() {
+        push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                item.sig.generics.clone(),
+                "Subgraph functions cannot be generic",
+            ),
+        );
+    }
+    if let syn::ReturnType::Type(_, ty) = &item.sig.output {
+        push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                ty.clone(),
+                "Subgraph functions cannot return a value; connect to their `#[output]` arguments instead",
+            ),
+        );
+    }
+
+    let mut graph = None;
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    for arg in item.sig.inputs.iter() {
+        let arg = match arg {
+            syn::FnArg::Typed(arg) => arg,
+            syn::FnArg::Receiver(receiver) => {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        receiver.clone(),
+                        "Subgraph functions cannot take `self`",
+                    ),
+                );
+                continue;
+            }
+        };
+        let Some(attr) = arg.attrs.first() else {
+            if graph.is_some() {
+                push_error(
+                    &mut errors,
+                    syn::Error::new_spanned(
+                        arg.pat.clone(),
+                        "Expected an `#[input]` or `#[output]` attribute on this argument, since the graph was already given",
+                    ),
+                );
+            }
+            graph = Some(arg);
+            continue;
+        };
+        let (kind, ports) = if attr.path().is_ident("input") {
+            ("Input", &mut inputs)
+        } else if attr.path().is_ident("output") {
+            ("Output", &mut outputs)
+        } else {
+            push_error(
+                &mut errors,
+                syn::Error::new_spanned(
+                    attr.path().clone(),
+                    "Unknown attribute. Only `input` and `output` are supported.",
+                ),
+            );
+            continue;
+        };
+        let name = arg_name(arg, attr, kind, None);
+        let ty = if kind == "Input" && !is_reference(&arg.ty) {
+            Ok(ungroup(&arg.ty).clone())
+        } else {
+            reference_elem(arg, kind, kind == "Output")
+        };
+        match (name, ty) {
+            (Ok(name), Ok(ty)) => ports.push(Port {
+                name,
+                pat: *arg.pat.clone(),
+                ty,
+            }),
+            (name, ty) => {
+                for err in [name.err(), ty.err()].into_iter().flatten() {
+                    push_error(&mut errors, err);
+                }
+            }
+        }
+    }
+    let Some(graph) = graph else {
+        push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                item.sig.clone(),
+                "Expected a `&Graph` argument to build the subgraph on",
+            ),
+        );
+        return errors.unwrap().to_compile_error().into();
+    };
+    if item.sig.inputs.len() == 1 {
+        push_error(
+            &mut errors,
+            syn::Error::new_spanned(
+                item.sig.clone(),
+                "A subgraph needs at least one `#[input]` or `#[output]` argument",
+            ),
+        );
+    }
+    if let Some(errors) = errors {
+        return errors.to_compile_error().into();
+    }
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let func_name = &item.sig.ident;
+    let struct_name = format_ident!("{}", func_name.to_string().to_case(Case::Pascal));
+    let module = format_ident!("__{}_subgraph", func_name);
+    let hidden = |name: &syn::Ident| format_ident!("__{}", name);
+
+    let input_names = inputs.iter().map(|port| &port.name).collect::<Vec<_>>();
+    let input_tys = inputs.iter().map(|port| &port.ty).collect::<Vec<_>>();
+    let output_names = outputs.iter().map(|port| &port.name).collect::<Vec<_>>();
+    let output_tys = outputs.iter().map(|port| &port.ty).collect::<Vec<_>>();
+    let hidden_outputs = output_names
+        .iter()
+        .map(|name| hidden(name))
+        .collect::<Vec<_>>();
+
+    // The inlet passes the subgraph's inputs on to the nodes built inside it, and the outlet
+    // collects their results as the subgraph's outputs.
+    let (inlet, add_inlet) = if inputs.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            passthrough(format_ident!("inlet"), &inputs, |name| name.clone(), hidden),
+            quote! {
+                let inlet = graph.node(#module::Inlet { #(#input_names: self.#input_names,)* });
+            },
+        )
+    };
+    let (outlet, add_outlet) = if outputs.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        (
+            passthrough(format_ident!("outlet"), &outputs, hidden, |name| {
+                name.clone()
+            }),
+            quote! {
+                let outlet = graph.node(#module::Outlet { #(#hidden_outputs: Default::default(),)* });
+            },
+        )
+    };
+    let nodes = match (inputs.is_empty(), outputs.is_empty()) {
+        (false, false) => quote! { (inlet, outlet) },
+        (false, true) => quote! { (inlet, inlet) },
+        _ => quote! { (outlet, outlet) },
+    };
+
+    let graph_pat = &graph.pat;
+    let graph_ty = &graph.ty;
+    let input_pats = inputs.iter().map(|port| &port.pat);
+    let output_pats = outputs.iter().map(|port| &port.pat);
+    let input_indices = 0..inputs.len();
+    let output_indices = 0..outputs.len();
+    let body = &item.block;
+    let port_indices = port_indices(
+        &input_names
+            .iter()
+            .map(|&name| name.clone())
+            .collect::<Vec<_>>(),
+        &output_names
+            .iter()
+            .map(|&name| name.clone())
+            .collect::<Vec<_>>(),
+    );
+
+    quote! {
+        #[doc(hidden)]
+        mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #inlet
+            #outlet
+        }
+
+        #(#attrs)*
+        #[derive(#(#extra_derives),*)]
+        #(#[#extra_attrs])*
+        #[allow(missing_docs)]
+        #vis struct #struct_name {
+            #(pub #input_names: #input_tys,)*
+        }
+
+        impl #struct_name {
+            #[doc = concat!("Adds a new ", stringify!(#struct_name), " subgraph to the graph and connects its inputs.")]
+            #[allow(unused)]
+            #[allow(clippy::too_many_arguments)]
+            #[track_caller]
+            #vis fn node(self, graph: &raug::graph::Graph, #(#input_names: impl raug::graph::node::IntoOutputOpt,)*) -> raug::graph::node::Node {
+                use raug::graph::node::IntoOutputOpt;
+                let (inputs, outputs) = self.__graph_node(graph);
+                let mut input_index = 0;
+                #(
+                    if let Some(input) = #input_names.into_output_opt(graph) {
+                        inputs.input(input_index).connect(input);
+                    }
+                    input_index += 1;
+                )*
+                outputs
+            }
+
+            #[doc = "Returns the input ports of the subgraph."]
+            #vis fn input_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                vec![#(raug::processor::io::SignalSpec::new(stringify!(#input_names), <#input_tys as raug::signal::Signal>::signal_type())),*]
+            }
+
+            #[doc = "Returns the output ports of the subgraph."]
+            #vis fn output_spec(&self) -> Vec<raug::processor::io::SignalSpec> {
+                vec![#(raug::processor::io::SignalSpec::new(stringify!(#output_names), <#output_tys as raug::signal::Signal>::signal_type())),*]
+            }
+        }
+
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        impl #struct_name {
+            #port_indices
+
+            pub fn __graph_node(self, graph: &raug::graph::Graph) -> (raug::graph::node::Node, raug::graph::node::Node) {
+                #[allow(clippy::too_many_arguments)]
+                fn #func_name(
+                    #graph_pat: #graph_ty,
+                    #(#input_pats: raug::graph::node::Output,)*
+                    #(#output_pats: raug::graph::node::Input,)*
+                ) #body
+
+                #add_inlet
+                #add_outlet
+                #func_name(graph, #(inlet.output(#input_indices),)* #(outlet.input(#output_indices),)*);
+                #nodes
+            }
+        }
+    }
+    .into()
+}
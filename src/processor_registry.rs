@@ -0,0 +1,81 @@
+use proc_macro::TokenStream;
+use quote::quote;
+
+struct ProcessorRegistryArgs {
+    inventory: Option<syn::Path>,
+}
+
+impl syn::parse::Parse for ProcessorRegistryArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut args = Self { inventory: None };
+        while !input.is_empty() {
+            let key = input.parse::<syn::Ident>()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "inventory" {
+                args.inventory = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "Unknown option. Only `inventory` is supported.",
+                ));
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(args)
+    }
+}
+
+pub fn processor_registry(input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(input as ProcessorRegistryArgs);
+    let inventory = args
+        .inventory
+        .map_or_else(|| quote! { ::inventory }, |path| quote! { #path });
+
+    quote! {
+        // `#[processor(register)]` submits registrations through this path, so the `inventory`
+        // crate only has to be reachable from the crate root.
+        #[doc(hidden)]
+        pub use #inventory as __processor_registry_inventory;
+
+        /// A processor registered with `#[processor(register)]`.
+        #[derive(Clone, Copy)]
+        pub struct ProcessorRegistration {
+            /// The name of the processor struct.
+            pub name: &'static str,
+            /// The module path the processor was defined in.
+            pub module_path: &'static str,
+            /// Returns the input spec of a default-constructed processor.
+            pub input_spec: fn() -> Vec<raug::processor::io::SignalSpec>,
+            /// Returns the output spec of a default-constructed processor.
+            pub output_spec: fn() -> Vec<raug::processor::io::SignalSpec>,
+            /// Creates a new default-constructed processor.
+            pub create: fn() -> Box<dyn raug::processor::Processor>,
+        }
+
+        __processor_registry_inventory::collect!(ProcessorRegistration);
+
+        /// The registry of all processors in this crate registered with `#[processor(register)]`.
+        pub struct ProcessorRegistry;
+
+        impl ProcessorRegistry {
+            /// Returns an iterator over all registered processors.
+            pub fn iter() -> impl Iterator<Item = &'static ProcessorRegistration> {
+                __processor_registry_inventory::iter::<ProcessorRegistration>.into_iter()
+            }
+
+            /// Returns the registration of the processor with the given name, if any.
+            pub fn get(name: &str) -> Option<&'static ProcessorRegistration> {
+                Self::iter().find(|registration| registration.name == name)
+            }
+
+            /// Creates a new instance of the processor with the given name, if any.
+            pub fn create(name: &str) -> Option<Box<dyn raug::processor::Processor>> {
+                Self::get(name).map(|registration| (registration.create)())
+            }
+        }
+    }
+    .into()
+}
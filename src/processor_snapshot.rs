@@ -0,0 +1,227 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse::Parser, punctuated::Punctuated};
+
+use crate::processor_test::{render, Render};
+
+pub fn processor_snapshot(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated.parse(attr)
+    {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+
+    let mut input = None;
+    let mut snapshot = None;
+    let mut frames = None;
+    let mut tolerance = None;
+    let mut sample_rate = None;
+    let mut block_size = None;
+    for arg in args.iter() {
+        let value = &arg.value;
+        if arg.path.is_ident("input") {
+            input = Some(value);
+        } else if arg.path.is_ident("snapshot") {
+            snapshot = Some(value);
+        } else if arg.path.is_ident("frames") {
+            frames = Some(value);
+        } else if arg.path.is_ident("tolerance") {
+            tolerance = Some(value);
+        } else if arg.path.is_ident("sample_rate") {
+            sample_rate = Some(value);
+        } else if arg.path.is_ident("block_size") {
+            block_size = Some(value);
+        } else {
+            return syn::Error::new_spanned(
+                &arg.path,
+                "Unknown attribute. Only `input`, `snapshot`, `frames`, `tolerance`, `sample_rate` and `block_size` are supported.",
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+
+    if !item.sig.inputs.is_empty() {
+        return syn::Error::new_spanned(
+            &item.sig.inputs,
+            "Snapshot functions take no arguments and return the processor to render",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if input.is_none() && frames.is_none() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`frames` is required when there is no `input`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let name = &item.sig.ident;
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let output = &item.sig.output;
+    let body = &item.block;
+
+    let input = input.map_or_else(|| quote! { None }, |input| quote! { Some(#input) });
+    let snapshot = snapshot.map_or_else(
+        || quote! { concat!("tests/snapshots/", stringify!(#name), ".csv") },
+        |snapshot| quote! { #snapshot },
+    );
+    let frames = frames.map_or_else(|| quote! { None }, |frames| quote! { Some(#frames) });
+    let tolerance = tolerance.map_or_else(|| quote! { 1e-6 }, |tolerance| quote! { #tolerance });
+    let sample_rate = sample_rate.map_or_else(|| quote! { 48_000.0 }, |rate| quote! { #rate });
+    let block_size = block_size.map_or_else(|| quote! { 512 }, |size| quote! { #size });
+
+    let render = render(&Render {
+        processor: quote! { processor },
+        sample_rate: quote! { sample_rate },
+        block_size: quote! { block_size },
+        bind_inputs: quote! {},
+        frames: quote! { frames },
+        block_inputs: quote! {
+            let blocks = inputs
+                .iter()
+                .map(|input| input.as_deref().map(|input| to_block(input, start, block_size)))
+                .collect::<Vec<_>>();
+            let blocks = blocks.iter().map(Option::as_ref).collect::<Vec<_>>();
+        },
+        connected: quote! { &blocks },
+        rendered_init: quote! { vec![Vec::<f32>::new(); rendered_ports.len()] },
+        collect_outputs: quote! {
+            for (rendered, &index) in rendered.iter_mut().zip(rendered_ports.iter()) {
+                rendered.extend_from_slice(read_output::<f32>(&outputs, index, len)?);
+            }
+        },
+    });
+
+    quote! {
+        #[test]
+        #(#attrs)*
+        #vis fn #name() {
+            use raug::processor::Processor;
+            use raug::signal::Signal;
+
+            fn processor() #output #body
+
+            fn read_csv(path: &std::path::Path) -> (Vec<String>, Vec<Vec<f32>>) {
+                let contents = std::fs::read_to_string(path)
+                    .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+                let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+                let names = lines
+                    .next()
+                    .map(|header| header.split(',').map(|name| name.trim().to_string()).collect::<Vec<_>>())
+                    .unwrap_or_default();
+                let mut columns = vec![Vec::new(); names.len()];
+                for (row, line) in lines.enumerate() {
+                    for (column, value) in columns.iter_mut().zip(line.split(',')) {
+                        column.push(value.trim().parse::<f32>().unwrap_or_else(|err| {
+                            panic!("Invalid sample on row {} of {}: {}", row + 1, path.display(), err)
+                        }));
+                    }
+                }
+                (names, columns)
+            }
+
+            fn write_csv(path: &std::path::Path, names: &[String], columns: &[Vec<f32>]) {
+                let mut contents = names.join(",");
+                contents.push('\n');
+                let frames = columns.iter().map(Vec::len).max().unwrap_or(0);
+                for frame in 0..frames {
+                    let row = columns
+                        .iter()
+                        .map(|column| column.get(frame).map(f32::to_string).unwrap_or_default())
+                        .collect::<Vec<_>>();
+                    contents.push_str(&row.join(","));
+                    contents.push('\n');
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).unwrap();
+                }
+                std::fs::write(path, contents)
+                    .unwrap_or_else(|err| panic!("Failed to write {}: {}", path.display(), err));
+            }
+
+            let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+            let input: Option<&str> = #input;
+            let snapshot = root.join(#snapshot);
+            let tolerance: f32 = #tolerance;
+            let sample_rate: f32 = #sample_rate;
+            let block_size: usize = #block_size;
+
+            let processor = processor();
+            let input_spec = processor.input_spec();
+            let output_spec = processor.output_spec();
+
+            // Input columns are matched to input ports by name.
+            let mut inputs = vec![None; input_spec.len()];
+            if let Some(input) = input {
+                let (names, columns) = read_csv(&root.join(input));
+                for (name, column) in names.into_iter().zip(columns) {
+                    let Some(index) = input_spec.iter().position(|spec| spec.name == name) else {
+                        panic!("{} has no input named `{}`", processor.name(), name);
+                    };
+                    inputs[index] = Some(column);
+                }
+            }
+            let frames: Option<usize> = #frames;
+            let frames = frames.unwrap_or_else(|| inputs.iter().flatten().map(Vec::len).max().unwrap_or(0));
+
+            // Only `f32` outputs are rendered into the snapshot.
+            let rendered_ports = (0..output_spec.len())
+                .filter(|&index| output_spec[index].signal_type == f32::signal_type())
+                .collect::<Vec<_>>();
+            let names = rendered_ports
+                .iter()
+                .map(|&index| output_spec[index].name.to_string())
+                .collect::<Vec<_>>();
+
+            let name = processor.name().to_string();
+            let rendered = match #render {
+                Ok(rendered) => rendered,
+                Err(err) => panic!("{} failed to render: {:?}", name, err),
+            };
+
+            if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+                write_csv(&snapshot, &names, &rendered);
+                return;
+            }
+            if !snapshot.exists() {
+                panic!(
+                    "The snapshot {} doesn't exist. Run the test with `UPDATE_SNAPSHOTS=1` to write it.",
+                    snapshot.display(),
+                );
+            }
+
+            let (expected_names, expected) = read_csv(&snapshot);
+            assert_eq!(
+                expected_names,
+                names,
+                "The output ports of {} don't match the snapshot {}",
+                name,
+                snapshot.display(),
+            );
+            for frame in 0..frames.max(expected.iter().map(Vec::len).max().unwrap_or(0)) {
+                for (port, (expected, rendered)) in expected.iter().zip(rendered.iter()).enumerate() {
+                    match (expected.get(frame), rendered.get(frame)) {
+                        (Some(expected), Some(rendered))
+                            if (expected - rendered).abs() <= tolerance
+                                || (expected.is_nan() && rendered.is_nan()) => {}
+                        (expected, rendered) => panic!(
+                            "{} differs from the snapshot {} at sample {} on output `{}`: expected {:?}, got {:?}",
+                            name,
+                            snapshot.display(),
+                            frame,
+                            names[port],
+                            expected,
+                            rendered,
+                        ),
+                    }
+                }
+            }
+        }
+    }
+    .into()
+}
@@ -0,0 +1,243 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+
+struct ProcessorTest {
+    processor: syn::Expr,
+    inputs: Vec<Option<syn::Expr>>,
+    output_types: Option<Vec<syn::Type>>,
+    sample_rate: Option<syn::Expr>,
+    block_size: Option<syn::Expr>,
+    frames: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for ProcessorTest {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let processor = input.parse()?;
+        let mut test = Self {
+            processor,
+            inputs: vec![],
+            output_types: None,
+            sample_rate: None,
+            block_size: None,
+            frames: None,
+        };
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<syn::Ident>()?;
+            input.parse::<syn::Token![=]>()?;
+            if key == "inputs" {
+                // Unconnected inputs are written as `_`.
+                let content;
+                syn::bracketed!(content in input);
+                let inputs = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated(&content)?;
+                test.inputs = inputs
+                    .into_iter()
+                    .map(|input| match input {
+                        syn::Expr::Infer(_) => None,
+                        input => Some(input),
+                    })
+                    .collect();
+            } else if key == "outputs" {
+                let content;
+                syn::bracketed!(content in input);
+                let types = Punctuated::<syn::Type, syn::Token![,]>::parse_terminated(&content)?;
+                test.output_types = Some(types.into_iter().collect());
+            } else if key == "sample_rate" {
+                test.sample_rate = Some(input.parse()?);
+            } else if key == "block_size" {
+                test.block_size = Some(input.parse()?);
+            } else if key == "frames" {
+                test.frames = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "Unknown option. Only `inputs`, `outputs`, `sample_rate`, `block_size` and `frames` are supported.",
+                ));
+            }
+        }
+        Ok(test)
+    }
+}
+
+pub fn processor_test(input: TokenStream) -> TokenStream {
+    let test = syn::parse_macro_input!(input as ProcessorTest);
+
+    let processor = &test.processor;
+    let sample_rate = test
+        .sample_rate
+        .as_ref()
+        .map_or_else(|| quote! { 48_000.0 }, |expr| quote! { #expr });
+    let block_size = test
+        .block_size
+        .as_ref()
+        .map_or_else(|| quote! { 64 }, |expr| quote! { #expr });
+
+    let mut bind_inputs = vec![];
+    let mut input_lens = vec![];
+    let mut block_inputs = vec![];
+    let mut connected = vec![];
+    for (i, input) in test.inputs.iter().enumerate() {
+        let samples = format_ident!("__samples{}", i);
+        let block = format_ident!("__block{}", i);
+        if let Some(input) = input {
+            bind_inputs.push(quote! {
+                let #samples: &[_] = &#input;
+            });
+            input_lens.push(quote! { #samples.len() });
+            block_inputs.push(quote! {
+                let #block = to_block(#samples, start, block_size);
+            });
+            connected.push(quote! { Some(&#block) });
+        } else {
+            connected.push(quote! { None });
+        }
+    }
+
+    let frames = match (&test.frames, input_lens.is_empty()) {
+        (Some(frames), _) => quote! { #frames },
+        (None, false) => quote! { [#(#input_lens),*].into_iter().max().unwrap_or(0) },
+        (None, true) => {
+            return syn::Error::new_spanned(
+                processor,
+                "`frames` is required when no inputs are connected",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+
+    // Without `outputs`, every output is read as `f32` into a `Vec<Vec<f32>>`.
+    let (rendered_init, collect_outputs) = match &test.output_types {
+        Some(types) => {
+            let indices = 0..types.len();
+            let fields = (0..types.len()).map(syn::Index::from).collect::<Vec<_>>();
+            (
+                quote! { (#(Vec::<#types>::new(),)*) },
+                quote! {
+                    #(rendered.#fields.extend_from_slice(read_output::<#types>(&outputs, #indices, len)?);)*
+                },
+            )
+        }
+        None => (
+            quote! { vec![Vec::<f32>::new(); output_spec.len()] },
+            quote! {
+                for (index, rendered) in rendered.iter_mut().enumerate() {
+                    rendered.extend_from_slice(read_output::<f32>(&outputs, index, len)?);
+                }
+            },
+        ),
+    };
+
+    render(&Render {
+        processor: quote! { #processor },
+        sample_rate,
+        block_size,
+        bind_inputs: quote! { #(#bind_inputs)* },
+        frames,
+        block_inputs: quote! { #(#block_inputs)* },
+        connected: quote! { &[#(#connected),*] },
+        rendered_init,
+        collect_outputs,
+    })
+    .into()
+}
+
+/// A block-by-block offline render, shared by `processor_test!` and `#[processor_snapshot]`.
+pub struct Render {
+    pub processor: proc_macro2::TokenStream,
+    pub sample_rate: proc_macro2::TokenStream,
+    pub block_size: proc_macro2::TokenStream,
+    /// Statements run before `frames` is evaluated.
+    pub bind_inputs: proc_macro2::TokenStream,
+    pub frames: proc_macro2::TokenStream,
+    /// Statements run for each block, with `start` and `block_size` in scope.
+    pub block_inputs: proc_macro2::TokenStream,
+    /// A `&[Option<&AnyBuffer>]` of the current block's inputs.
+    pub connected: proc_macro2::TokenStream,
+    pub rendered_init: proc_macro2::TokenStream,
+    /// Statements appending the current block's `outputs` to `rendered`, with `len` in scope.
+    pub collect_outputs: proc_macro2::TokenStream,
+}
+
+/// Generates an expression that allocates the processor, renders it block by block, and evaluates
+/// to a `ProcResult` of `rendered`.
+pub fn render(render: &Render) -> proc_macro2::TokenStream {
+    let Render {
+        processor,
+        sample_rate,
+        block_size,
+        bind_inputs,
+        frames,
+        block_inputs,
+        connected,
+        rendered_init,
+        collect_outputs,
+    } = render;
+
+    quote! {
+        (|| -> raug::processor::ProcResult<_> {
+            use raug::processor::Processor;
+            use raug::signal::type_erased::AnyBuffer;
+
+            #[allow(dead_code)]
+            fn to_block<T: raug::signal::Signal>(samples: &[T], start: usize, block_size: usize) -> AnyBuffer {
+                let mut block = AnyBuffer::zeros::<T>(block_size);
+                if let Some(block) = block.as_mut_slice::<T>() {
+                    for (sample, input) in block.iter_mut().zip(samples.iter().skip(start)) {
+                        *sample = Clone::clone(input);
+                    }
+                }
+                block
+            }
+
+            fn read_output<T: raug::signal::Signal>(outputs: &[AnyBuffer], index: usize, len: usize) -> raug::processor::ProcResult<&[T]> {
+                outputs
+                    .get(index)
+                    .and_then(|output| output.as_slice::<T>())
+                    .and_then(|output| output.get(..len))
+                    .ok_or_else(|| raug::processor::ProcessorError::ProcessorSpecific(format!(
+                        "Expected output {} to be a buffer of {:?} with at least {} samples",
+                        index,
+                        <T as raug::signal::Signal>::signal_type(),
+                        len,
+                    )))
+            }
+
+            let mut processor = #processor;
+            let sample_rate: f32 = #sample_rate;
+            let block_size: usize = #block_size;
+            #bind_inputs
+            let frames: usize = #frames;
+
+            let input_spec = processor.input_spec();
+            let output_spec = processor.output_spec();
+            processor.allocate(sample_rate, block_size);
+            processor.resize_buffers(sample_rate, block_size);
+            let mut outputs = processor.create_output_buffers(block_size);
+
+            let mut rendered = #rendered_init;
+            let mut start = 0;
+            while start < frames {
+                let len = block_size.min(frames - start);
+                #block_inputs
+                let env = raug::processor::io::ProcEnv {
+                    sample_rate,
+                    block_size: len,
+                    mode: raug::processor::io::ProcessMode::Block,
+                };
+                processor.process(
+                    raug::processor::io::ProcessorInputs::new(&input_spec, #connected, env),
+                    raug::processor::io::ProcessorOutputs::new(&output_spec, &mut outputs, raug::processor::io::ProcessMode::Block),
+                )?;
+                #collect_outputs
+                start += len;
+            }
+
+            Ok(rendered)
+        })()
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use raug::processor::ProcessorError;
+use raug_macros::{bench_processor, processor};
+
+#[processor(derive(Default))]
+pub fn half_gain(#[input] x: &f32) -> f32 {
+    x * 0.5
+}
+
+#[processor(derive(Default))]
+pub fn fails(#[input] x: &f32) -> ProcResult<f32> {
+    if *x > 0.5 {
+        return Err(ProcessorError::ProcessorSpecific("too loud".to_string()));
+    }
+    Ok(*x)
+}
+
+bench_processor!(HalfGain, block_size = 64, baseline = |x: f32| x * 0.5);
+bench_processor!(
+    Fails,
+    name = bench_failing,
+    processor = Fails::default(),
+    block_size = 32,
+    sample_rate = 1_000.0,
+);
+
+/// A criterion configuration that only takes a few samples, so the benchmarks run quickly.
+fn quick_criterion() -> criterion::Criterion {
+    criterion::Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(1))
+        .measurement_time(Duration::from_millis(10))
+        .without_plots()
+}
+
+#[test]
+fn runs_process_and_baseline() {
+    bench_half_gain(&mut quick_criterion());
+}
+
+#[test]
+#[should_panic(expected = "Fails failed")]
+fn panics_on_process_errors() {
+    bench_failing(&mut quick_criterion());
+}
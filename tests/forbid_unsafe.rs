@@ -0,0 +1,33 @@
+//! Generated processors must compile in crates that forbid `unsafe` code.
+//!
+//! The one exception is `flush_denormals`, whose MXCSR intrinsics require `unsafe`. Using it in
+//! such a crate is an error pointing at the flag, as checked by
+//! `tests/ui/flush_denormals_forbid_unsafe.rs`.
+#![forbid(unsafe_code)]
+
+use raug_macros::processor;
+
+#[processor(derive(Default), outputs(low, high))]
+pub fn split(#[state] y: &mut f32, #[input] x: f32, #[output] dry: &mut f32) -> (f32, f32) {
+    *dry = x;
+    *y += (x - *y) * 0.1;
+    (*y, x - *y)
+}
+
+#[processor(derive(Clone, Default), poly = 2, channels = 2)]
+pub fn pluck(#[input] note: f32, #[input] gate: bool) -> f32 {
+    if gate {
+        note
+    } else {
+        0.0
+    }
+}
+
+#[test]
+fn generated_code_is_safe() {
+    use raug::processor::Processor;
+
+    assert_eq!(Split::default().output_spec().len(), 3);
+    assert_eq!(PluckMulti::default().output_spec().len(), 2);
+    assert_eq!(PluckPoly::new(Pluck::default).output_spec().len(), 1);
+}
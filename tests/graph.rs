@@ -0,0 +1,117 @@
+use raug::builtins::math::{Abs, Add, Clamp, Gt, Max, Mul, Neg, Rem};
+use raug::graph::node::{IntoOutputOpt, Output};
+use raug::graph::Graph;
+use raug::processor::Processor;
+use raug_macros::{graph, processor};
+
+#[processor(derive(Default))]
+pub fn sine_osc(#[state] phase: &mut f32, #[input] freq: &f32) -> f32 {
+    *phase += freq / 48_000.0;
+    (*phase * std::f32::consts::TAU).sin()
+}
+
+#[processor(derive(Default))]
+pub fn lowpass(#[state] y: &mut f32, #[input] x: &f32, #[input] cutoff: &f32) -> f32 {
+    *y += (x - *y) * (cutoff / 48_000.0).min(1.0);
+    *y
+}
+
+#[processor(derive(Default), outputs(sum, difference))]
+pub fn mid_side(#[input] left: &f32, #[input] right: &f32) -> (f32, f32) {
+    (left + right, left - right)
+}
+
+#[test]
+fn port_indices() {
+    assert_eq!(Lowpass::__INPUT_COUNT, 2);
+    assert_eq!(Lowpass::__input_x, 0);
+    assert_eq!(Lowpass::__input_cutoff, 1);
+    assert_eq!(MidSide::__output_sum, 0);
+    assert_eq!(MidSide::__output_difference, 1);
+}
+
+/// Asserts that `graph` has as many nodes and edges as the hand-built `expected` graph.
+fn assert_same_shape(graph: &Graph, expected: &Graph) {
+    assert_eq!(graph.node_count(), expected.node_count());
+    assert_eq!(graph.edge_count(), expected.edge_count());
+}
+
+#[test]
+fn builds_new_graph() {
+    let graph = graph! {
+        osc = SineOsc(freq: 440.0);
+        flt = Lowpass(osc.out, cutoff: 1000.0);
+        dac <- flt.out;
+    };
+
+    let expected = Graph::new();
+    let osc = SineOsc::default().node(&expected, 440.0);
+    let flt = Lowpass::default().node(&expected, osc.output(0), 1000.0);
+    expected.add_audio_output().input(0).connect(flt.output(0));
+    assert_same_shape(&graph, &expected);
+}
+
+#[test]
+fn builds_into_existing_graph() {
+    let graph = Graph::new();
+    graph! { graph =>
+        lfo = SineOsc(2.0);
+        left = SineOsc(freq: 220.0);
+        right = SineOsc(freq: 330.0);
+        ms = MidSide(right: right, left: left.out);
+        flt = Lowpass(ms.sum);
+        flt.cutoff <- lfo.out;
+        dac <- flt, ms.difference;
+    }
+
+    let expected = Graph::new();
+    let lfo = SineOsc::default().node(&expected, 2.0);
+    let left = SineOsc::default().node(&expected, 220.0);
+    let right = SineOsc::default().node(&expected, 330.0);
+    let ms = MidSide::default().node(&expected, left.output(0), right.output(0));
+    let flt = Lowpass::default().node(&expected, ms.output(0), lfo.output(0));
+    expected.add_audio_output().input(0).connect(flt.output(0));
+    expected.add_audio_output().input(0).connect(ms.output(1));
+    assert_same_shape(&graph, &expected);
+}
+
+/// Adds a math node to `graph`, connected to `inputs`, and returns its output.
+fn math_node<P: Processor + Default>(graph: &Graph, inputs: &[Output]) -> Output {
+    let node = graph.node(P::default());
+    for (index, input) in inputs.iter().enumerate() {
+        node.input(index).connect(input.clone());
+    }
+    node.output(0)
+}
+
+#[test]
+fn builds_math_nodes() {
+    let gain = 0.5f32;
+    let graph = graph! {
+        lfo = SineOsc(freq: 2 * 0.25);
+        osc = SineOsc(freq: 440.0 + lfo.out * 10);
+        flt = Lowpass(-osc.out * gain.powi(2), cutoff: (lfo.out.abs() * 1000.0).clamp(20, 20_000.0f32));
+        dac <- flt.out % 1.0, flt.out.max(1.0.sin()) * (flt.out > 0.5);
+    };
+
+    // Literal subexpressions are folded, so only operations on node outputs add nodes.
+    let expected = Graph::new();
+    let constant = |value: f32| value.into_output_opt(&expected).unwrap();
+    let lfo = SineOsc::default().node(&expected, 0.5);
+    let scaled = math_node::<Mul>(&expected, &[lfo.output(0), constant(10.0)]);
+    let freq = math_node::<Add>(&expected, &[constant(440.0), scaled]);
+    let osc = SineOsc::default().node(&expected, freq);
+    let neg = math_node::<Neg>(&expected, &[osc.output(0)]);
+    let x = math_node::<Mul>(&expected, &[neg, constant(gain.powi(2))]);
+    let abs = math_node::<Abs>(&expected, &[lfo.output(0)]);
+    let scaled = math_node::<Mul>(&expected, &[abs, constant(1000.0)]);
+    let cutoff = math_node::<Clamp>(&expected, &[scaled, constant(20.0), constant(20_000.0)]);
+    let flt = Lowpass::default().node(&expected, x, cutoff);
+    let rem = math_node::<Rem>(&expected, &[flt.output(0), constant(1.0)]);
+    let max = math_node::<Max>(&expected, &[flt.output(0), constant(1.0f32.sin())]);
+    let gate = math_node::<Gt>(&expected, &[flt.output(0), constant(0.5)]);
+    let gated = math_node::<Mul>(&expected, &[max, gate]);
+    expected.add_audio_output().input(0).connect(rem);
+    expected.add_audio_output().input(0).connect(gated);
+    assert_same_shape(&graph, &expected);
+}
@@ -0,0 +1,135 @@
+use raug::{
+    processor::{
+        io::{ProcEnv, ProcessMode, ProcessorInputs, ProcessorOutputs, SignalSpec},
+        ProcResult,
+    },
+    signal::{type_erased::AnyBuffer, Signal},
+};
+use raug_macros::iter_proc_io_as;
+
+const BLOCK_SIZE: usize = 4;
+
+fn env() -> ProcEnv {
+    ProcEnv {
+        sample_rate: 48_000.0,
+        block_size: BLOCK_SIZE,
+        mode: ProcessMode::Block,
+    }
+}
+
+fn specs(names: &[&str]) -> Vec<SignalSpec> {
+    names
+        .iter()
+        .map(|name| SignalSpec::new(*name, f32::signal_type()))
+        .collect()
+}
+
+fn filled(value: f32) -> AnyBuffer {
+    let mut buffer = AnyBuffer::zeros::<f32>(BLOCK_SIZE);
+    buffer.as_mut_slice::<f32>().unwrap().fill(value);
+    buffer
+}
+
+fn scale(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for frame in iter_proc_io_as!(
+        inputs as [x: f32, gain: f32, sidechain: raug::signal::Any],
+        outputs as [out: f32]
+    ) {
+        let _ = frame.sidechain;
+        *frame.out = frame.x.copied().unwrap_or_default() * frame.gain.copied().unwrap_or(1.0);
+    }
+    Ok(())
+}
+
+#[test]
+fn named_slots() {
+    let input_spec = specs(&["x", "gain", "sidechain"]);
+    let output_spec = specs(&["out"]);
+    let (x, gain) = (filled(2.0), filled(3.0));
+    let mut outputs = vec![AnyBuffer::zeros::<f32>(BLOCK_SIZE)];
+
+    scale(
+        ProcessorInputs::new(&input_spec, &[Some(&x), Some(&gain), None], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[6.0; BLOCK_SIZE]);
+
+    scale(
+        ProcessorInputs::new(&input_spec, &[Some(&x), None, None], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[2.0; BLOCK_SIZE]);
+}
+
+fn chunked_sum(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for (a, b, out) in iter_proc_io_as!(inputs as [f32, f32], outputs as [f32], chunks = 3) {
+        assert!(out.len() <= 3);
+        out.copy_from_slice(a.unwrap_or(&[0.0; 3][..out.len()]));
+        if let Some(b) = b {
+            for (out, b) in out.iter_mut().zip(b) {
+                *out += b;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn chunked_slots() {
+    let input_spec = specs(&["a", "b"]);
+    let output_spec = specs(&["out"]);
+    let (a, b) = (filled(1.0), filled(0.5));
+    let mut outputs = vec![AnyBuffer::zeros::<f32>(BLOCK_SIZE)];
+
+    chunked_sum(
+        ProcessorInputs::new(&input_spec, &[Some(&a), Some(&b)], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[1.5; BLOCK_SIZE]);
+
+    chunked_sum(
+        ProcessorInputs::new(&input_spec, &[None, Some(&b)], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[0.5; BLOCK_SIZE]);
+}
+
+fn optional_inputs(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for frame in iter_proc_io_as!(
+        inputs as [x: Option<f32>, gain: f32 default = 0.25],
+        outputs as [out: f32]
+    ) {
+        let x: Option<f32> = frame.x;
+        *frame.out = match x {
+            Some(x) => x * frame.gain,
+            None => -frame.gain,
+        };
+    }
+    Ok(())
+}
+
+#[test]
+fn optional_and_default_slots() {
+    let input_spec = specs(&["x", "gain"]);
+    let output_spec = specs(&["out"]);
+    let (x, gain) = (filled(2.0), filled(3.0));
+    let mut outputs = vec![AnyBuffer::zeros::<f32>(BLOCK_SIZE)];
+
+    optional_inputs(
+        ProcessorInputs::new(&input_spec, &[Some(&x), Some(&gain)], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[6.0; BLOCK_SIZE]);
+
+    optional_inputs(
+        ProcessorInputs::new(&input_spec, &[None, None], env()),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    )
+    .unwrap();
+    assert_eq!(outputs[0].as_slice::<f32>().unwrap(), &[-0.25; BLOCK_SIZE]);
+}
@@ -0,0 +1,126 @@
+use raug::{
+    processor::{
+        io::{ProcEnv, ProcessMode, ProcessorInputs, ProcessorOutputs},
+        ProcResult, Processor, ProcessorError,
+    },
+    signal::type_erased::AnyBuffer,
+};
+use raug_macros::{iter_proc_io_as, processor};
+
+const BLOCK_SIZE: usize = 4;
+
+#[processor(derive(Default))]
+pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+    x * g
+}
+
+#[processor(derive(Clone, Default), poly = 2)]
+pub fn blip(#[input] note: &f32, #[input] gate: &bool) -> f32 {
+    if *gate {
+        *note
+    } else {
+        0.0
+    }
+}
+
+/// Runs one block of `processor` with the given buffers, returning the error message if it fails.
+fn process(
+    processor: &mut impl Processor,
+    inputs: &[Option<&AnyBuffer>],
+    outputs: &mut [AnyBuffer],
+) -> Result<(), String> {
+    let input_spec = processor.input_spec();
+    let output_spec = processor.output_spec();
+    let env = ProcEnv {
+        sample_rate: 48_000.0,
+        block_size: BLOCK_SIZE,
+        mode: ProcessMode::Block,
+    };
+    let result = processor.process(
+        ProcessorInputs::new(&input_spec, inputs, env),
+        ProcessorOutputs::new(&output_spec, outputs, ProcessMode::Block),
+    );
+    match result {
+        Ok(()) => Ok(()),
+        Err(ProcessorError::ProcessorSpecific(message)) => Err(message),
+        Err(err) => panic!("Unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn matching_buffers() {
+    let mut gain = Gain::default();
+    let x = AnyBuffer::zeros::<f32>(BLOCK_SIZE);
+    let mut outputs = gain.create_output_buffers(BLOCK_SIZE);
+    assert_eq!(process(&mut gain, &[Some(&x), None], &mut outputs), Ok(()));
+}
+
+#[test]
+fn input_type_mismatch() {
+    let mut gain = Gain::default();
+    let x = AnyBuffer::zeros::<bool>(BLOCK_SIZE);
+    let mut outputs = gain.create_output_buffers(BLOCK_SIZE);
+    let err = process(&mut gain, &[Some(&x), None], &mut outputs).unwrap_err();
+    assert!(err.starts_with("Gain: input `x`"), "{}", err);
+}
+
+#[test]
+fn input_too_short() {
+    let mut gain = Gain::default();
+    let g = AnyBuffer::zeros::<f32>(BLOCK_SIZE - 1);
+    let mut outputs = gain.create_output_buffers(BLOCK_SIZE);
+    let err = process(&mut gain, &[None, Some(&g)], &mut outputs).unwrap_err();
+    assert!(err.starts_with("Gain: input `g`"), "{}", err);
+}
+
+#[test]
+fn output_type_mismatch() {
+    let mut gain = Gain::default();
+    let mut outputs = vec![AnyBuffer::zeros::<bool>(BLOCK_SIZE)];
+    let err = process(&mut gain, &[None, None], &mut outputs).unwrap_err();
+    assert!(err.starts_with("Gain: output `out`"), "{}", err);
+}
+
+#[test]
+fn wrong_buffer_counts() {
+    let mut gain = Gain::default();
+    let mut outputs = gain.create_output_buffers(BLOCK_SIZE);
+    let err = process(&mut gain, &[None], &mut outputs).unwrap_err();
+    assert_eq!(err, "Gain: expected 2 inputs, got 1");
+    let err = process(&mut gain, &[None, None], &mut []).unwrap_err();
+    assert_eq!(err, "Gain: expected 1 outputs, got 0");
+}
+
+#[test]
+fn poly_output_mismatch() {
+    let mut poly = BlipPoly::new(Blip::default);
+    let mut outputs = vec![AnyBuffer::zeros::<f32>(BLOCK_SIZE - 1)];
+    let err = process(&mut poly, &[None, None], &mut outputs).unwrap_err();
+    assert!(err.starts_with("BlipPoly: output `out`"), "{}", err);
+}
+
+fn sum(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for (a, b, out) in iter_proc_io_as!(inputs as [f32, f32], outputs as [f32]) {
+        *out = a.copied().unwrap_or_default() + b.copied().unwrap_or_default();
+    }
+    Ok(())
+}
+
+#[test]
+fn iter_proc_io_as_count_mismatch() {
+    let input_spec = Gain::default().input_spec();
+    let output_spec = Gain::default().output_spec();
+    let env = ProcEnv {
+        sample_rate: 48_000.0,
+        block_size: BLOCK_SIZE,
+        mode: ProcessMode::Block,
+    };
+    let mut outputs = vec![AnyBuffer::zeros::<f32>(BLOCK_SIZE)];
+    let result = sum(
+        ProcessorInputs::new(&input_spec, &[None], env),
+        ProcessorOutputs::new(&output_spec, &mut outputs, ProcessMode::Block),
+    );
+    assert!(
+        matches!(result, Err(ProcessorError::ProcessorSpecific(message)) if message == "Expected 2 inputs, got 1")
+    );
+}
@@ -1,4 +1,4 @@
-use raug_macros::processor;
+use raug_macros::{processor, processor_test};
 
 #[processor(derive(Clone, Copy, Debug, Default))]
 pub fn add_to_counter(
@@ -21,3 +21,330 @@ impl AddToCounter {
         }
     }
 }
+
+#[processor(derive(Clone, std::fmt::Debug, Default), attr(allow(dead_code)))]
+pub fn passthrough(#[input] a: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+#[processor(derive(Default))]
+pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+    x * g
+}
+
+#[processor(derive(Default), outputs(left, right))]
+pub fn pan(#[input] x: &f32, #[input] pan: &f32) -> ProcResult<(f32, f32)> {
+    Ok((x * (1.0 - pan), x * pan))
+}
+
+#[processor(derive(Default), outputs(wet))]
+pub fn split_gain(#[input] x: &f32, #[output] dry: &mut f32) -> f32 {
+    *dry = *x;
+    x * 0.5
+}
+
+#[test]
+fn returned_outputs() {
+    let outputs = processor_test!(Gain::default(), inputs = [[1.0, 2.0], [3.0; 2]]).unwrap();
+    assert_eq!(outputs, [[3.0, 6.0]]);
+    let outputs = processor_test!(Pan::default(), inputs = [[2.0; 2], [0.25, 0.5]]).unwrap();
+    assert_eq!(outputs, [[1.5, 1.0], [0.5, 1.0]]);
+    let outputs = processor_test!(SplitGain::default(), inputs = [[1.0, -2.0]]).unwrap();
+    assert_eq!(outputs, [[1.0, -2.0], [0.5, -1.0]]);
+}
+
+#[processor(derive(Default))]
+pub fn mix(#[input] a: f32, #[input] b: f32, #[input] label: &String) -> f32 {
+    let _ = label;
+    a + b
+}
+
+#[processor(derive(Default))]
+pub fn phasor(
+    #[state] phase: &mut f32,
+    #[state(name = range)] (lo, hi): &mut (f32, f32),
+    #[input] mut freq: f32,
+    #[input] _unused: &f32,
+    env: ProcEnv,
+) -> f32 {
+    freq = freq.max(0.0);
+    *phase = (*phase + freq / env.sample_rate) % 1.0;
+    *lo + *phase * (*hi - *lo)
+}
+
+#[processor(derive(Default))]
+pub fn lfo(
+    #[input] freq: f32,
+    #[time] t: f64,
+    #[sample_index] i: usize,
+    #[block_size] n: usize,
+    env: raug::processor::io::ProcEnv,
+) -> f32 {
+    let _ = (i, n, env);
+    (std::f64::consts::TAU * freq as f64 * t).sin() as f32
+}
+
+mod clock {
+    use raug_macros::processor;
+
+    #[processor(derive(Default))]
+    pub fn clock(#[input] offset: f32, #[time] t: f64, #[sample_index] i: usize) -> (f32, f32) {
+        (offset + t as f32, i as f32)
+    }
+}
+
+#[test]
+fn time_restarts_on_allocate() {
+    let clock = clock::Clock {
+        offset: 1.0,
+        __elapsed_samples: 48,
+    };
+    let (t, i) = processor_test!(
+        clock,
+        inputs = [_],
+        outputs = [f32, f32],
+        sample_rate = 4.0,
+        block_size = 2,
+        frames = 4,
+    )
+    .unwrap();
+    assert_eq!(t, [1.0, 1.25, 1.5, 1.75]);
+    assert_eq!(i, [0.0, 1.0, 0.0, 1.0]);
+}
+
+#[processor(derive(Default), oversample = 4)]
+pub fn saturate(#[input] x: f32, #[input] drive: f32, #[time] t: f64) -> f32 {
+    let _ = t;
+    (x * drive).tanh()
+}
+
+mod drive {
+    use raug_macros::processor;
+
+    #[processor(derive(Default), oversample = 2)]
+    pub fn drive(#[input] x: f32, #[input] gain: f32) -> f32 {
+        (x * gain).tanh()
+    }
+}
+
+#[test]
+fn oversampled_struct_literal() {
+    let drive = drive::Drive {
+        gain: 2.0,
+        ..Default::default()
+    };
+    let outputs = processor_test!(drive, inputs = [[0.5; 16], _], block_size = 4).unwrap();
+    assert_eq!(outputs[0].len(), 16);
+    assert!(outputs[0].iter().all(|sample| sample.is_finite()));
+}
+
+#[processor(derive(Clone, Default), poly = 8, voice_stealing = "quietest")]
+pub fn voice(
+    #[state] phase: &mut f32,
+    #[state] level: &mut f32,
+    #[input] note: f32,
+    #[input] gate: bool,
+    #[input] decay: f32,
+    env: ProcEnv,
+) -> f32 {
+    let freq = 440.0 * 2f32.powf((note - 69.0) / 12.0);
+    *phase = (*phase + freq / env.sample_rate) % 1.0;
+    *level = if gate { 1.0 } else { *level * decay };
+    (*phase * std::f32::consts::TAU).sin() * *level
+}
+
+#[processor(
+    derive(Clone, Default),
+    poly = 4,
+    voice_stealing = "round_robin",
+    poly_outputs = "per_voice"
+)]
+pub fn gate_follower(#[input] note: f32, #[input] gate: f32) -> (f32, bool) {
+    (note, gate > 0.0)
+}
+
+#[test]
+fn poly_voices() {
+    let poly = VoicePoly::new(Voice::default);
+    assert_eq!(poly.voices.len(), 8);
+    let per_voice = GateFollowerPoly::new(GateFollower::default);
+    assert_eq!(raug::processor::Processor::output_spec(&per_voice).len(), 8);
+}
+
+#[processor(derive(Clone, Default), poly = 2, poly_outputs = "per_voice")]
+pub fn held_note(#[input] note: f32, #[input] gate: bool) -> f32 {
+    if gate {
+        note
+    } else {
+        -note
+    }
+}
+
+#[processor(
+    derive(Clone, Default),
+    poly = 2,
+    voice_stealing = "quietest",
+    poly_outputs = "per_voice"
+)]
+pub fn decaying_note(#[state] level: &mut f32, #[input] note: f32, #[input] gate: bool) -> f32 {
+    if gate {
+        *level = note;
+    } else {
+        *level *= 0.5;
+    }
+    *level
+}
+
+#[test]
+fn poly_allocates_oldest_voice() {
+    // Each legato note releases the previous one and takes the voice released longest ago.
+    let outputs = processor_test!(
+        HeldNotePoly::new(HeldNote::default),
+        inputs = [[1.0, 2.0, 3.0], [true; 3]],
+        block_size = 1,
+    )
+    .unwrap();
+    assert_eq!(outputs, [[1.0, -1.0, 3.0], [0.0, 2.0, -2.0]]);
+}
+
+#[test]
+fn poly_steals_quietest_voice() {
+    // The third note takes the second voice, whose tail is quieter than the first voice's.
+    let outputs = processor_test!(
+        DecayingNotePoly::new(DecayingNote::default),
+        inputs = [[8.0, 1.0, 3.0], [true; 3]],
+        block_size = 1,
+    )
+    .unwrap();
+    assert_eq!(outputs, [[8.0, 4.0, 2.0], [0.0, 1.0, 3.0]]);
+}
+
+#[processor(derive(Clone, Debug, Default), channels = 2)]
+pub fn one_pole(
+    #[state] y: &mut f32,
+    #[state(shared)] coeff_cache: &mut f32,
+    #[input] x: f32,
+    #[input] coeff: &f32,
+) -> f32 {
+    *coeff_cache = *coeff;
+    *y += (x - *y) * *coeff_cache;
+    *y
+}
+
+#[processor(
+    derive(Clone, Debug, Default),
+    channels = 2,
+    flush_denormals,
+    guard_nan = "zero"
+)]
+pub fn comb(#[state] y: &mut f32, #[input] x: f32, #[input] feedback: f32) -> f32 {
+    *y = x + *y * feedback;
+    *y
+}
+
+#[processor(derive(Default), flush_denormals, guard_nan = "error")]
+pub fn reciprocal(#[input] x: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+    *out = 1.0 / x;
+    Ok(())
+}
+
+#[test]
+fn multichannel_ports() {
+    use raug::processor::Processor;
+
+    let stereo = OnePoleMulti::default();
+    let inputs = stereo.input_spec();
+    let names = inputs
+        .iter()
+        .map(|spec| spec.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["x_l", "x_r", "coeff_l", "coeff_r"]);
+    assert_eq!(stereo.output_spec().len(), 2);
+}
+
+#[processor(derive(Default), bypass, mix, dry = x)]
+pub fn fold(#[input] drive: &f32, #[input] x: &f32) -> f32 {
+    (x * drive).sin()
+}
+
+#[test]
+fn bypass_and_mix_ports() {
+    use raug::processor::Processor;
+
+    let fold = Fold::default();
+    assert_eq!(fold.mix, 1.0);
+    let inputs = fold.input_spec();
+    let names = inputs
+        .iter()
+        .map(|spec| spec.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["drive", "x", "bypass", "mix"]);
+}
+
+#[test]
+fn bypass_and_mix_render() {
+    let x = [0.5, -1.0, 2.0];
+    let wet = x.map(|x: f32| x.sin());
+
+    let outputs = processor_test!(Fold::default(), inputs = [[1.0; 3], x, _, _]).unwrap();
+    assert_eq!(outputs, [wet]);
+
+    let outputs = processor_test!(Fold::default(), inputs = [[1.0; 3], x, [true; 3], _]).unwrap();
+    assert_eq!(outputs, [x]);
+
+    let half: [f32; 3] = std::array::from_fn(|i| x[i] * 0.5 + wet[i] * 0.5);
+    let outputs = processor_test!(Fold::default(), inputs = [[1.0; 3], x, _, [0.5; 3]]).unwrap();
+    assert_eq!(outputs, [half]);
+}
+
+#[processor(derive(Default), realtime)]
+pub fn clip(#[input] x: &f32, #[input] ceiling: &f32, #[input] label: &String) -> f32 {
+    let _ = label.len();
+    x.clamp(-ceiling, *ceiling)
+}
+
+#[processor(derive(Default), fuzz, bypass)]
+pub fn resonator(
+    #[state] y1: &mut f32,
+    #[state] y2: &mut f32,
+    #[input] x: &f32,
+    #[input] freq: &f32,
+    #[input] damping: &f32,
+    env: ProcEnv,
+) -> f32 {
+    let w = std::f32::consts::TAU * freq.abs() * 1000.0 / env.sample_rate;
+    let r = 0.5 + 0.49 * damping.abs().min(1.0);
+    let y = x + 2.0 * r * w.cos() * *y1 - r * r * *y2;
+    *y2 = *y1;
+    *y1 = y;
+    y
+}
+
+#[test]
+fn fuzz_resonator() {
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..64 {
+        let data = (0..256)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                seed as u8
+            })
+            .collect::<Vec<_>>();
+        Resonator::default().fuzz_process(&data);
+    }
+}
+
+#[processor(derive(Default), fuzz)]
+pub fn broken(#[input] x: &f32) -> f32 {
+    x / 0.0 * 0.0
+}
+
+#[test]
+#[should_panic(expected = "Broken produced NaN")]
+fn fuzz_catches_non_finite_outputs() {
+    // A leading zero byte selects bounded inputs, so every output must be finite.
+    Broken::default().fuzz_process(&[0, 1, 2, 3, 4, 5, 6, 7]);
+}
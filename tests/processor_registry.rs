@@ -0,0 +1,61 @@
+use raug::processor::Processor;
+use raug_macros::{processor, processor_registry};
+
+processor_registry!();
+
+#[processor(register, derive(Default))]
+pub fn gain(#[input] x: &f32, #[input] g: &f32) -> f32 {
+    x * g
+}
+
+#[processor(register, derive(Default), outputs(left, right))]
+pub fn pan(#[input] x: &f32, #[input] pan: &f32) -> (f32, f32) {
+    (x * (1.0 - pan), x * pan)
+}
+
+mod nested {
+    use raug_macros::processor;
+
+    #[processor(register, derive(Default))]
+    pub fn invert(#[input] x: &f32) -> f32 {
+        -x
+    }
+}
+
+fn port_names(specs: &[raug::processor::io::SignalSpec]) -> Vec<&str> {
+    specs.iter().map(|spec| spec.name.as_str()).collect()
+}
+
+#[test]
+fn iterates_registered_processors() {
+    let mut names = ProcessorRegistry::iter()
+        .map(|registration| registration.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["Gain", "Invert", "Pan"]);
+
+    let invert = ProcessorRegistry::get("Invert").unwrap();
+    assert_eq!(invert.module_path, module_path!().to_string() + "::nested");
+}
+
+#[test]
+fn looks_up_specs_by_name() {
+    let pan = ProcessorRegistry::get("Pan").unwrap();
+    assert_eq!(pan.name, "Pan");
+    assert_eq!(port_names(&(pan.input_spec)()), ["x", "pan"]);
+    assert_eq!(port_names(&(pan.output_spec)()), ["left", "right"]);
+    assert_eq!(
+        port_names(&(pan.input_spec)()),
+        port_names(&Pan::default().input_spec())
+    );
+    assert!(ProcessorRegistry::get("Missing").is_none());
+}
+
+#[test]
+fn creates_processors_by_name() {
+    let gain = ProcessorRegistry::create("Gain").unwrap();
+    assert_eq!(gain.name(), "Gain");
+    assert_eq!(port_names(&gain.input_spec()), ["x", "g"]);
+    assert_eq!(port_names(&gain.output_spec()), ["out"]);
+    assert!(ProcessorRegistry::create("Missing").is_none());
+}
@@ -0,0 +1,27 @@
+use raug_macros::{processor, processor_snapshot};
+
+#[processor(derive(Default))]
+pub fn smooth(#[state] y: &mut f32, #[input] x: &f32, #[input] g: &f32) -> f32 {
+    *y += (x * g - *y) * 0.5;
+    *y
+}
+
+#[processor(derive(Default))]
+pub fn impulse(#[state] fired: &mut bool) -> f32 {
+    if *fired {
+        0.0
+    } else {
+        *fired = true;
+        1.0
+    }
+}
+
+#[processor_snapshot(input = "tests/inputs/ramp.csv", block_size = 5)]
+fn smooth_ramp() -> Smooth {
+    Smooth::default()
+}
+
+#[processor_snapshot(frames = 8, snapshot = "tests/snapshots/impulse.csv")]
+fn impulse_response() -> Impulse {
+    Impulse::default()
+}
@@ -0,0 +1,50 @@
+use raug_macros::{processor, processor_test};
+
+#[processor(derive(Default))]
+pub fn ramp(#[state] value: &mut f32, #[input] step: &f32) -> (f32, bool) {
+    *value += step;
+    (*value, *value > 2.0)
+}
+
+#[processor(derive(Default))]
+pub fn counter(#[state] count: &mut f32, #[sample_index] i: usize) -> f32 {
+    *count += 1.0;
+    i as f32
+}
+
+#[test]
+fn renders_across_blocks() {
+    let (values, over) = processor_test!(
+        Ramp::default(),
+        inputs = [[1.0; 5]],
+        outputs = [f32, bool],
+        block_size = 2,
+    )
+    .unwrap();
+    assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(over, [false, false, true, true, true]);
+}
+
+#[test]
+fn renders_without_inputs() {
+    let outputs = processor_test!(Counter::default(), block_size = 3, frames = 7).unwrap();
+    assert_eq!(outputs, [[0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 0.0]]);
+}
+
+#[test]
+fn unconnected_inputs() {
+    let (values, _) = processor_test!(
+        Ramp::default(),
+        inputs = [_],
+        outputs = [f32, bool],
+        frames = 2
+    )
+    .unwrap();
+    assert_eq!(values, [0.0, 0.0]);
+}
+
+#[test]
+fn mismatched_output_types() {
+    let result = processor_test!(Ramp::default(), inputs = [_], frames = 2);
+    assert!(result.is_err());
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use raug::graph::Graph;
+use raug::signal::Signal;
+use raug_macros::{graph, processor, subgraph};
+
+#[processor(derive(Default))]
+pub fn sine_osc(#[state] phase: &mut f32, #[input] freq: &f32) -> f32 {
+    *phase += freq / 48_000.0;
+    (*phase * std::f32::consts::TAU).sin()
+}
+
+#[processor(derive(Default))]
+pub fn vca(#[input] x: &f32, #[input] gate: &bool) -> f32 {
+    if *gate {
+        *x
+    } else {
+        0.0
+    }
+}
+
+/// A sine voice with a gate.
+#[subgraph(derive(Default))]
+pub fn voice(
+    graph: &Graph,
+    #[input] freq: &f32,
+    #[input] gate: &bool,
+    #[output] out: &mut f32,
+    #[output] osc_out: &mut f32,
+) {
+    let osc = SineOsc::default().node(graph, freq);
+    out.connect(Vca::default().node(graph, osc, gate).output(0));
+    osc_out.connect(osc.output(0));
+}
+
+#[processor(derive(Default))]
+pub fn scale(#[input] x: &f32, #[input] g: &f32) -> f32 {
+    x * g
+}
+
+/// Scales its input, and passes the gain straight through to an output.
+#[subgraph(derive(Default))]
+pub fn scaler(
+    graph: &Graph,
+    #[input] x: &f32,
+    #[input] gain: &f32,
+    #[output] scaled: &mut f32,
+    #[output] gain_out: &mut f32,
+) {
+    scaled.connect(Scale::default().node(graph, x, gain).output(0));
+    gain_out.connect(gain);
+}
+
+#[subgraph]
+fn noise(graph: &Graph, #[output] out: &mut f32) {
+    out.connect(
+        SineOsc {
+            freq: 1.0,
+            ..Default::default()
+        }
+        .node(graph, ())
+        .output(0),
+    );
+}
+
+#[test]
+fn spec() {
+    let voice = Voice {
+        freq: 220.0,
+        gate: true,
+    };
+    let input_spec = voice.input_spec();
+    assert_eq!(input_spec[0].name, "freq");
+    assert_eq!(input_spec[1].name, "gate");
+    assert_eq!(input_spec[1].signal_type, bool::signal_type());
+    let output_spec = voice.output_spec();
+    assert_eq!(output_spec[0].name, "out");
+    assert_eq!(output_spec[1].name, "osc_out");
+    assert_eq!(Voice::__output_osc_out, 1);
+}
+
+#[test]
+fn node() {
+    let graph = Graph::new();
+    let voice = Voice::default().node(&graph, 440.0, ());
+    let noise = Noise {}.node(&graph);
+    Vca::default().node(&graph, voice.output(Voice::__output_out), ());
+    Vca::default().node(&graph, noise, ());
+}
+
+#[test]
+fn in_graph_macro() {
+    let _graph = graph! {
+        lfo = SineOsc(freq: 1.0);
+        voice = Voice(freq: 220.0 + lfo.out * 10.0);
+        voice.gate <- lfo.out > 0.0;
+        dac <- voice.out * 0.5, voice.osc_out;
+    };
+}
+
+#[test]
+fn renders_inputs_to_outputs() {
+    let graph = Graph::new();
+    // `gain` is left unconnected, so the subgraph uses the value of its field.
+    let scaler = Scaler {
+        gain: 0.5,
+        ..Default::default()
+    }
+    .node(&graph, 2.0, ());
+    graph
+        .add_audio_output()
+        .input(0)
+        .connect(scaler.output(Scaler::__output_scaled));
+    graph
+        .add_audio_output()
+        .input(0)
+        .connect(scaler.output(Scaler::__output_gain_out));
+
+    let outputs = graph
+        .run_offline(Duration::from_secs_f64(4.0 / 48_000.0), 48_000.0, 4)
+        .unwrap();
+    assert_eq!(outputs, [[1.0; 4], [0.5; 4]]);
+}
@@ -0,0 +1,14 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn passthrough(
+    #[state] count: &f32,
+    #[state] phase: f32,
+    #[input] a: &mut f32,
+    #[output] out: &f32,
+    #[output] out2: f32,
+) -> ProcResult<()> {
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn length(#[input] s: String) -> f32 {
+    s.len() as f32
+}
+
+fn main() {}
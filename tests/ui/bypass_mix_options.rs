@@ -0,0 +1,26 @@
+use raug_macros::processor;
+
+#[processor(bypass)]
+pub fn silence(#[input] x: &f32) {}
+
+#[processor(mix, wet = y)]
+pub fn invert(#[input] x: &f32) -> f32 {
+    -x
+}
+
+#[processor(mix)]
+pub fn threshold(#[input] x: &f32) -> bool {
+    *x > 0.0
+}
+
+#[processor(dry = x)]
+pub fn identity(#[input] x: &f32) -> f32 {
+    *x
+}
+
+#[processor(mix, oversample = 2)]
+pub fn drive(#[input] x: &f32) -> f32 {
+    x.tanh()
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn smooth(#[state(shared)] y: &mut f32, #[input] x: f32) -> f32 {
+    *y = x;
+    *y
+}
+
+#[processor(channels = 2, oversample = 2)]
+pub fn drive(#[input] x: f32) -> f32 {
+    x.tanh()
+}
+
+#[processor(channels = 1)]
+pub fn mono(#[input] x: f32) -> f32 {
+    x
+}
+
+#[allow(dead_code)]
+fn reset<T>(_: &mut T, _: f32, _: usize) {}
+
+#[processor(channels = 2, allocate = reset, resize_buffers = reset)]
+pub fn filter(#[input] x: f32) -> f32 {
+    x
+}
+
+fn main() {}
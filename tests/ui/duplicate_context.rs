@@ -0,0 +1,8 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn counter(#[sample_index] i: usize, #[sample_index] j: usize, #[tim] t: f64) -> f32 {
+    (i + j) as f32 + t as f32
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn passthrough(
+    env: ProcEnv,
+    env2: ProcEnv,
+    #[input] a: &f32,
+    #[output] out: &mut f32,
+) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+fn main() {}
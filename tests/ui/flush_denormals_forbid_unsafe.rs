@@ -0,0 +1,11 @@
+#![forbid(unsafe_code)]
+
+use raug_macros::processor;
+
+#[processor(flush_denormals)]
+pub fn feedback(#[state] y: &mut f32, #[input] x: &f32) -> f32 {
+    *y = x + *y * 0.5;
+    *y
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use raug_macros::{graph, processor};
+
+#[processor(derive(Default))]
+pub fn lowpass(#[input] x: &f32, #[input] cutoff: &f32) -> f32 {
+    x * cutoff
+}
+
+fn main() {
+    let _graph = graph! {
+        a = Lowpass(1.0, 1000.0, 0.5);
+        b = Lowpass(1.0, x: 2.0);
+    };
+
+    let _positional_after_named = graph! {
+        a = Lowpass(cutoff: 1000.0, 1.0);
+    };
+}
@@ -0,0 +1,14 @@
+use raug_macros::{graph, processor};
+
+#[processor(derive(Default))]
+pub fn lowpass(#[input] x: &f32, #[input] cutoff: &f32) -> f32 {
+    x * cutoff
+}
+
+fn main() {
+    let _graph = graph! {
+        a = Lowpass(x: 1.0);
+        b = Lowpass(a.out << 2, cutoff: a.out.log10());
+        dac <- a.out.clamp(0.0), a.out.sin(1.0);
+    };
+}
@@ -0,0 +1,25 @@
+use raug_macros::{graph, processor};
+
+#[processor(derive(Default))]
+pub fn lowpass(#[input] x: &f32, #[input] cutoff: &f32) -> f32 {
+    x * cutoff
+}
+
+fn main() {
+    let _unknown_ports = graph! {
+        a = Lowpass(cutof: 1000.0);
+        b = Lowpass(a.output);
+        b.q <- 0.5;
+    };
+
+    let _unknown_node = graph! {
+        a = Lowpass(x: 1.0, x: 2.0);
+        c.cutoff <- a.out;
+        dac = Lowpass;
+    };
+
+    let _missing_port = graph! {
+        a = Lowpass;
+        out <- a.out;
+    };
+}
@@ -0,0 +1,8 @@
+use raug_macros::processor;
+
+#[processor(guard_nan = "clamp")]
+pub fn feedback(#[input] x: &f32) -> f32 {
+    *x
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+use raug::processor::{io::*, ProcResult};
+use raug_macros::iter_proc_io_as;
+
+fn output_default(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [f32], outputs as [f32 default = 0.0]) {}
+    Ok(())
+}
+
+fn optional_output(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [f32], outputs as [Option<f32>]) {}
+    Ok(())
+}
+
+fn chunked_default(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [f32 default = 1.0], outputs as [f32], chunks = 16) {}
+    Ok(())
+}
+
+fn chunked_option(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [Option<f32>], outputs as [f32], chunks = 16) {}
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use raug::processor::{io::*, ProcResult};
+use raug_macros::iter_proc_io_as;
+
+fn mixed(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [freq: f32, f32], outputs as [out: f32]) {}
+    Ok(())
+}
+
+fn duplicate(inputs: ProcessorInputs, outputs: ProcessorOutputs) -> ProcResult<()> {
+    for _ in iter_proc_io_as!(inputs as [x: f32], outputs as [x: f32]) {}
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use raug_macros::processor;
+
+#[processor(derive(Clone, 3), allocate = 1 + 2, resize_buffers = "resize")]
+pub fn passthrough(#[input] a: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+fn main() {}
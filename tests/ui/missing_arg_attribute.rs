@@ -0,0 +1,9 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn passthrough(a: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use raug_macros::processor;
+
+#[processor(outputs(left, right, center))]
+pub fn pan(#[input] x: &f32, #[input] pan: &f32) -> (f32, f32) {
+    (x * (1.0 - pan), x * pan)
+}
+
+fn main() {}
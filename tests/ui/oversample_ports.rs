@@ -0,0 +1,13 @@
+use raug_macros::processor;
+
+#[processor(oversample = 2)]
+pub fn gate(#[input] x: &f32, #[input] open: &bool, #[output] active: &mut bool) -> f32 {
+    *active = *open;
+    if *open {
+        *x
+    } else {
+        0.0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use raug_macros::processor;
+
+#[processor(poly = 4)]
+pub fn voice(#[input] note: f32) -> f32 {
+    note
+}
+
+#[processor(voice_stealing = "oldest")]
+pub fn mono(#[input] note: f32) -> f32 {
+    note
+}
+
+#[processor(poly = 2, poly_outputs = "mono")]
+pub fn other(#[input] note: f32, #[input] gate: bool) -> f32 {
+    note
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use raug_macros::processor;
+
+#[processor(realtime)]
+pub fn logger(
+    #[state] history: &mut Vec<f32>,
+    #[input] label: &String,
+    #[input] x: &f32,
+) -> f32 {
+    let _scratch: Vec<f32> = Vec::new();
+    let _reserved: Vec<f32> = Vec::with_capacity(history.len());
+    let _boxed = Box::new(*x);
+    let _label = label.clone();
+    println!("{}", format!("{}", x));
+    *x
+}
+
+fn main() {}
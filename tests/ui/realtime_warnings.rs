@@ -0,0 +1,22 @@
+#![deny(deprecated)]
+
+use raug_macros::processor;
+
+#[processor(realtime)]
+pub fn logger(
+    #[state] history: &mut [f32; 4],
+    #[state] lock: &mut std::sync::Mutex<f32>,
+    #[input] x: &f32,
+) -> f32 {
+    history.rotate_left(1);
+    let _name = x.to_string();
+    *lock.lock().unwrap()
+}
+
+#[processor(realtime)]
+#[allow(deprecated)]
+pub fn copied(#[input] x: &f32) -> f32 {
+    x.to_owned()
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use raug_macros::processor;
+
+#[processor(register)]
+pub fn passthrough<T: raug::signal::Signal>(#[input] a: &T, #[output] out: &mut T) -> ProcResult<()> {
+    *out = a.clone();
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn split(#[input] x: &f32, #[output] out: &mut f32) -> f32 {
+    *out = *x;
+    -x
+}
+
+#[processor(outputs(dry, wet))]
+pub fn blend(#[input] x: &f32, #[output] dry: &mut f32) -> (f32, f32) {
+    *dry = *x;
+    (*x, -x)
+}
+
+fn main() {}
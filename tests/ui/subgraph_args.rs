@@ -0,0 +1,18 @@
+use raug::graph::Graph;
+use raug_macros::subgraph;
+
+#[subgraph(derive(Default), voices = 4)]
+fn returns(graph: &Graph, #[input] x: &f32) -> f32 {
+    0.0
+}
+
+#[subgraph]
+fn bad_ports(graph: &Graph, extra: &Graph, #[state] s: &mut f32, #[output] out: &f32) {}
+
+#[subgraph]
+fn no_graph(#[input] x: &f32) {}
+
+#[subgraph]
+fn no_ports(graph: &Graph) {}
+
+fn main() {}
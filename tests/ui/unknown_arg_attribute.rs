@@ -0,0 +1,9 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn passthrough(#[inputs] a: &f32, #[ouput] out: &mut f32, #[foo] b: &f32) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use raug_macros::processor;
+
+#[processor(derives(Clone), registr, alocate = allocate, mix(0.5), fuz = true)]
+pub fn passthrough(#[input] a: &f32, #[output] out: &mut f32) -> ProcResult<()> {
+    *out = *a;
+    Ok(())
+}
+
+fn main() {}
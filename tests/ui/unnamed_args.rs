@@ -0,0 +1,12 @@
+use raug_macros::processor;
+
+#[processor]
+pub fn passthrough(
+    #[state] (a, b): &mut (f32, f32),
+    #[input(label = "x")] x: &f32,
+    #[output] [out]: &mut [f32; 1],
+) -> ProcResult<()> {
+    Ok(())
+}
+
+fn main() {}